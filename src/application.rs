@@ -0,0 +1,73 @@
+//! Run iced applications on top of `solstice`.
+//!
+//! The event loop, window resizes, scale factor changes, redraw requests and
+//! [`Command`] execution are all handled by [`iced_glutin`], which drives the
+//! [`window::Compositor`] of this crate.
+//!
+//! [`Command`]: crate::Command
+//! [`window::Compositor`]: crate::window::Compositor
+use crate::window;
+
+pub use iced_glutin::{Application, Error};
+
+/// The executor used to run the futures of a [`Command`].
+///
+/// [`Command`]: crate::Command
+pub type Executor = iced_futures::executor::ThreadPool;
+
+/// The settings of an [`Application`] running on `solstice`.
+#[derive(Debug, Clone)]
+pub struct Settings<Flags> {
+    /// The settings of the window and the data needed to initialize the
+    /// [`Application`].
+    pub application: iced_glutin::Settings<Flags>,
+
+    /// The settings of the renderer.
+    pub renderer: crate::Settings,
+}
+
+impl<Flags> Settings<Flags> {
+    /// Creates the default [`Settings`] using the given flags to initialize
+    /// the [`Application`].
+    pub fn with_flags(flags: Flags) -> Self {
+        let default = Settings::<()>::default();
+
+        Self {
+            application: iced_glutin::Settings {
+                id: default.application.id,
+                window: default.application.window,
+                flags,
+                exit_on_close_request: default.application.exit_on_close_request,
+                try_opengles_first: default.application.try_opengles_first,
+            },
+            renderer: default.renderer,
+        }
+    }
+}
+
+impl<Flags> Default for Settings<Flags>
+where
+    Flags: Default,
+{
+    fn default() -> Self {
+        Self {
+            application: iced_glutin::Settings::default(),
+            renderer: crate::Settings::default(),
+        }
+    }
+}
+
+/// Runs an [`Application`] with an executor, compositor, and the provided
+/// settings.
+///
+/// This function does not return until the window of the [`Application`] is
+/// closed.
+pub fn run<A>(settings: Settings<A::Flags>) -> Result<(), Error>
+where
+    A: Application<Renderer = crate::Renderer> + 'static,
+{
+    iced_glutin::application::run::<A, Executor, window::Compositor>(
+        settings.application,
+        settings.renderer,
+    )
+}
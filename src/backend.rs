@@ -1,16 +1,19 @@
+use crate::decoration::{Decoration, Decorations};
+#[cfg(any(feature = "image", feature = "svg"))]
+use crate::image;
 use crate::quad;
 use crate::text;
 use crate::triangle;
+use crate::settings::ColorSpace;
 use crate::{Settings, Transformation, Viewport};
 use iced_graphics::font;
 use iced_graphics::Layer;
 use iced_graphics::Primitive;
 use iced_graphics::{backend, Point};
 use iced_native::text::Hit;
-use iced_native::{
-    alignment::{Horizontal as HorizontalAlignment, Vertical as VerticalAlignment},
-    Font, Size,
-};
+use iced_native::{Font, Size};
+use solstice::canvas::Canvas;
+use std::cell::RefCell;
 
 /// A [`glow`] graphics backend for [`iced`].
 ///
@@ -21,21 +24,56 @@ pub struct Backend {
     quad_pipeline: quad::Pipeline,
     text_pipeline: text::Pipeline,
     triangle_pipeline: triangle::Pipeline,
+    #[cfg(any(feature = "image", feature = "svg"))]
+    image_pipeline: image::Pipeline,
+    decorations: RefCell<Decorations>,
     default_text_size: u16,
+    color_space: ColorSpace,
 }
 
 impl Backend {
     /// Creates a new [`Backend`].
     pub fn new(gl: &mut solstice::Context, settings: Settings) -> Self {
-        let text_pipeline = text::Pipeline::new(gl, settings.default_font);
-        let quad_pipeline = quad::Pipeline::new(gl);
-        let triangle_pipeline = triangle::Pipeline::new(gl);
+        // Default fonts given as bytes hold a single face
+        let default_font = settings.default_font.map(|bytes| (bytes, 0));
+
+        #[cfg(feature = "system_fonts")]
+        let default_font = default_font.or_else(|| {
+            let descriptor = settings.default_font_family.as_ref()?;
+
+            match crate::font::load_face(descriptor) {
+                Ok(face) => Some((face.bytes, face.index)),
+                Err(error) => {
+                    log::warn!("Default font failed to load: {}", error);
+
+                    None
+                }
+            }
+        });
+
+        let text_pipeline = text::Pipeline::new(
+            gl,
+            default_font,
+            settings.glyph_cache,
+            settings.font_fallback,
+            settings.color_space,
+        );
+        let quad_pipeline = quad::Pipeline::new(gl, settings.color_space);
+        let triangle_pipeline =
+            triangle::Pipeline::new(gl, settings.antialiasing, settings.color_space);
+
+        #[cfg(any(feature = "image", feature = "svg"))]
+        let image_pipeline = image::Pipeline::new(gl, settings.color_space);
 
         Self {
             quad_pipeline,
             text_pipeline,
             triangle_pipeline,
+            #[cfg(any(feature = "image", feature = "svg"))]
+            image_pipeline,
+            decorations: RefCell::new(Decorations::default()),
             default_text_size: settings.default_text_size,
+            color_space: settings.color_space,
         }
     }
 
@@ -49,35 +87,139 @@ impl Backend {
         primitives: &[Primitive],
         viewport: &Viewport,
         overlay_text: &[T],
+    ) {
+        self.render(gl, None, primitives, viewport, overlay_text);
+    }
+
+    /// Draws the provided primitives into an offscreen framebuffer and reads
+    /// the resulting pixels back.
+    ///
+    /// This is useful for producing thumbnails, exporting snapshots of a user
+    /// interface or testing the renderer on a headless context.
+    ///
+    /// An empty [`Snapshot`] is returned for a viewport without any pixels.
+    pub fn render_offscreen<T: AsRef<str>>(
+        &mut self,
+        gl: &mut solstice::Context,
+        primitives: &[Primitive],
+        viewport: &Viewport,
+        overlay_text: &[T],
+    ) -> Snapshot {
+        let Size { width, height } = viewport.physical_size();
+
+        if width == 0 || height == 0 {
+            return Snapshot {
+                width,
+                height,
+                pixels: Vec::new(),
+            };
+        }
+
+        // An sRGB canvas encodes linear colors as they are blended, before
+        // they are quantized
+        let format = match self.color_space {
+            ColorSpace::Srgb => solstice::PixelFormat::SRGBA8,
+            ColorSpace::LegacyGamma => solstice::PixelFormat::RGBA8,
+        };
+
+        let canvas = Canvas::new(
+            gl,
+            solstice::canvas::Settings {
+                width: width as _,
+                height: height as _,
+                format,
+                ..Default::default()
+            },
+        )
+        .expect("Create offscreen target");
+
+        solstice::Renderer::clear(
+            gl,
+            solstice::ClearSettings {
+                color: Some(solstice::Color::new(0.0, 0.0, 0.0, 0.0).into()),
+                target: Some(&canvas),
+                ..Default::default()
+            },
+        );
+
+        self.render(gl, Some(&canvas), primitives, viewport, overlay_text);
+
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        gl.read_pixels(
+            Some(&canvas),
+            solstice::viewport::Viewport::new(0, 0, width as i32, height as i32),
+            solstice::PixelFormat::RGBA8,
+            &mut pixels,
+        );
+
+        // OpenGL reads rows bottom to top
+        let stride = (width * 4) as usize;
+        let pixels = pixels
+            .chunks_exact(stride)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        Snapshot {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Stores a [`Decoration`] until the next frame and returns its index.
+    pub(crate) fn register_decoration(&self, decoration: Decoration) -> Option<usize> {
+        self.decorations.borrow_mut().register(decoration)
+    }
+
+    fn render<T: AsRef<str>>(
+        &mut self,
+        gl: &mut solstice::Context,
+        target: Option<&Canvas>,
+        primitives: &[Primitive],
+        viewport: &Viewport,
+        overlay_text: &[T],
     ) {
         let viewport_size = viewport.physical_size();
         let scale_factor = viewport.scale_factor() as f32;
         let projection = viewport.projection();
 
+        self.decorations.get_mut().start_frame();
+
         let mut layers = Layer::generate(primitives, viewport);
         layers.push(Layer::overlay(overlay_text, viewport));
 
         for layer in layers {
-            self.flush(gl, scale_factor, projection, &layer, viewport_size.height);
+            self.flush(gl, target, scale_factor, projection, &layer, viewport_size);
         }
+
+        self.quad_pipeline.end_frame(gl);
+
+        #[cfg(any(feature = "image", feature = "svg"))]
+        self.image_pipeline.trim_cache();
     }
 
     fn flush(
         &mut self,
         gl: &mut solstice::Context,
+        target: Option<&Canvas>,
         scale_factor: f32,
         transformation: Transformation,
         layer: &Layer<'_>,
-        target_height: u32,
+        target_size: Size<u32>,
     ) {
+        let target_height = target_size.height;
         let mut bounds = (layer.bounds * scale_factor).snap();
-        bounds.height = bounds.height.min(target_height);
+        bounds.height = bounds.height.min(target_height.saturating_sub(bounds.y));
 
         if !layer.quads.is_empty() {
             self.quad_pipeline.draw(
                 gl,
+                target,
                 target_height,
                 &layer.quads,
+                &self.decorations.borrow(),
                 transformation,
                 scale_factor,
                 bounds,
@@ -88,68 +230,37 @@ impl Backend {
             let scaled = transformation * Transformation::scale(scale_factor, scale_factor);
 
             self.triangle_pipeline
-                .draw(gl, target_height, scaled, scale_factor, &layer.meshes);
+                .draw(gl, target, target_size, scaled, scale_factor, &layer.meshes);
+        }
+
+        #[cfg(any(feature = "image", feature = "svg"))]
+        {
+            if !layer.images.is_empty() {
+                self.image_pipeline.draw(
+                    gl,
+                    target,
+                    target_height,
+                    &layer.images,
+                    transformation,
+                    scale_factor,
+                    bounds,
+                );
+            }
         }
 
         if !layer.text.is_empty() {
             for text in layer.text.iter() {
-                // Target physical coordinates directly to avoid blurry text
-                let text = solstice_glyph::Section {
-                    // TODO: We `round` here to avoid rerasterizing text when
-                    // its position changes slightly. This can make text feel a
-                    // bit "jumpy". We may be able to do better once we improve
-                    // our text rendering/caching pipeline.
-                    screen_position: (
-                        (text.bounds.x * scale_factor).round(),
-                        (text.bounds.y * scale_factor).round(),
-                    ),
-                    // TODO: Fix precision issues with some scale factors.
-                    //
-                    // The `ceil` here can cause some words to render on the
-                    // same line when they should not.
-                    //
-                    // Ideally, `wgpu_glyph` should be able to compute layout
-                    // using logical positions, and then apply the proper
-                    // scaling when rendering. This would ensure that both
-                    // measuring and rendering follow the same layout rules.
-                    bounds: (
-                        (text.bounds.width * scale_factor).ceil(),
-                        (text.bounds.height * scale_factor).ceil(),
-                    ),
-                    text: vec![solstice_glyph::Text {
-                        text: text.content,
-                        scale: solstice_glyph::ab_glyph::PxScale {
-                            x: text.size * scale_factor,
-                            y: text.size * scale_factor,
-                        },
-                        font_id: self.text_pipeline.find_font(text.font),
-                        extra: solstice_glyph::Extra {
-                            color: text.color,
-                            z: 0.0,
-                        },
-                    }],
-                    layout: solstice_glyph::Layout::default()
-                        .h_align(match text.horizontal_alignment {
-                            HorizontalAlignment::Left => solstice_glyph::HorizontalAlign::Left,
-                            HorizontalAlignment::Center => solstice_glyph::HorizontalAlign::Center,
-                            HorizontalAlignment::Right => solstice_glyph::HorizontalAlign::Right,
-                        })
-                        .v_align(match text.vertical_alignment {
-                            VerticalAlignment::Top => solstice_glyph::VerticalAlign::Top,
-                            VerticalAlignment::Center => solstice_glyph::VerticalAlign::Center,
-                            VerticalAlignment::Bottom => solstice_glyph::VerticalAlign::Bottom,
-                        }),
-                };
-
-                self.text_pipeline.queue(text);
+                self.text_pipeline
+                    .queue(text, scale_factor, self.color_space.encode(text.color));
             }
 
             self.text_pipeline.draw_queued(
                 gl,
+                target,
                 transformation,
                 solstice_glyph::Region {
                     x: bounds.x,
-                    y: target_height - (bounds.y + bounds.height),
+                    y: target_height.saturating_sub(bounds.y + bounds.height),
                     width: bounds.width,
                     height: bounds.height,
                 },
@@ -158,9 +269,23 @@ impl Backend {
     }
 }
 
+/// The pixels of an offscreen rendering produced by
+/// [`Backend::render_offscreen`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The width of the snapshot, in physical pixels.
+    pub width: u32,
+
+    /// The height of the snapshot, in physical pixels.
+    pub height: u32,
+
+    /// The RGBA pixels of the snapshot, row by row from the top.
+    pub pixels: Vec<u8>,
+}
+
 impl iced_graphics::Backend for Backend {
     fn trim_measurements(&mut self) {
-        self.text_pipeline.trim_measurement_cache()
+        self.text_pipeline.trim_measurement_cache();
     }
 }
 
@@ -193,14 +318,14 @@ impl backend::Text for Backend {
 
 #[cfg(feature = "image")]
 impl backend::Image for Backend {
-    fn dimensions(&self, _handle: &iced_native::image::Handle) -> (u32, u32) {
-        (50, 50)
+    fn dimensions(&self, handle: &iced_native::image::Handle) -> (u32, u32) {
+        self.image_pipeline.dimensions(handle)
     }
 }
 
 #[cfg(feature = "svg")]
 impl backend::Svg for Backend {
-    fn viewport_dimensions(&self, _handle: &iced_native::svg::Handle) -> (u32, u32) {
-        (50, 50)
+    fn viewport_dimensions(&self, handle: &iced_native::svg::Handle) -> (u32, u32) {
+        self.image_pipeline.viewport_dimensions(handle)
     }
 }
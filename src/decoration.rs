@@ -0,0 +1,643 @@
+//! Decorate quads beyond what the built-in style sheets allow.
+//!
+//! The style sheets of `iced_native` widgets can only fill quads with a
+//! solid color with the same radius for every corner and a uniform border. A
+//! [`Decoration`] also supports gradients, shadows, independent corner radii
+//! and styled borders with different widths and colors per side. Custom
+//! widgets can draw one with the [`Decorate`] extension of the [`Renderer`],
+//! and the style sheets of the built-in widgets through a [`Handle`]:
+//!
+//! ```no_run
+//! # use iced_solstice::Renderer;
+//! # use iced_native::{Color, Rectangle};
+//! use iced_solstice::decoration::{ColorStop, Decorate, Decoration, Fill, Gradient};
+//!
+//! # fn draw(renderer: &mut Renderer, bounds: Rectangle) {
+//! renderer.fill_decorated(
+//!     bounds,
+//!     &Decoration {
+//!         fill: Fill::Gradient(Gradient::linear(
+//!             std::f32::consts::FRAC_PI_2,
+//!             vec![
+//!                 ColorStop::new(0.0, Color::from_rgb(0.2, 0.4, 0.9)),
+//!                 ColorStop::new(1.0, Color::from_rgb(0.1, 0.2, 0.5)),
+//!             ],
+//!         )),
+//!         border_radius: 4.0.into(),
+//!         ..Decoration::default()
+//!     },
+//! );
+//! # }
+//! ```
+//!
+//! [`Renderer`]: crate::Renderer
+use crate::Renderer;
+use iced_graphics::layer;
+use iced_native::renderer::{self, Renderer as _};
+use iced_native::{Color, Point, Rectangle, Vector};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+/// The maximum amount of [`ColorStop`]s of a [`Gradient`].
+///
+/// Any additional stops are ignored, with a warning the first time.
+pub const MAX_STOPS: usize = 4;
+
+/// The appearance of a decorated quad.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoration {
+    /// How the inside of the quad is filled.
+    pub fill: Fill,
+
+    /// The radii of the corners of the quad.
+    pub border_radius: BorderRadius,
+
+    /// The [`Border`] of the quad.
+    pub border: Border,
+
+    /// The [`Shadow`] cast by the quad, if any.
+    pub shadow: Option<Shadow>,
+}
+
+impl Default for Decoration {
+    fn default() -> Self {
+        Decoration {
+            fill: Fill::Color(Color::TRANSPARENT),
+            border_radius: BorderRadius::default(),
+            border: Border::default(),
+            shadow: None,
+        }
+    }
+}
+
+/// The radii of the corners of a decorated quad, in logical pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BorderRadius {
+    /// The radius of the top left corner.
+    pub top_left: f32,
+
+    /// The radius of the top right corner.
+    pub top_right: f32,
+
+    /// The radius of the bottom right corner.
+    pub bottom_right: f32,
+
+    /// The radius of the bottom left corner.
+    pub bottom_left: f32,
+}
+
+impl BorderRadius {
+    /// Returns the radius shared by every corner, if they are all equal.
+    pub fn uniform(self) -> Option<f32> {
+        let BorderRadius {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        } = self;
+
+        if top_left == top_right && top_left == bottom_right && top_left == bottom_left {
+            Some(top_left)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<f32> for BorderRadius {
+    fn from(radius: f32) -> Self {
+        BorderRadius {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+}
+
+impl From<[f32; 4]> for BorderRadius {
+    /// Creates a [`BorderRadius`] from the radii of the top left, top right,
+    /// bottom right and bottom left corners.
+    fn from([top_left, top_right, bottom_right, bottom_left]: [f32; 4]) -> Self {
+        BorderRadius {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        }
+    }
+}
+
+impl From<BorderRadius> for [f32; 4] {
+    fn from(radius: BorderRadius) -> Self {
+        [
+            radius.top_left,
+            radius.top_right,
+            radius.bottom_right,
+            radius.bottom_left,
+        ]
+    }
+}
+
+/// The border of a decorated quad, drawn inside of its bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Border {
+    /// The top [`Edge`] of the border.
+    pub top: Edge,
+
+    /// The right [`Edge`] of the border.
+    pub right: Edge,
+
+    /// The bottom [`Edge`] of the border.
+    pub bottom: Edge,
+
+    /// The left [`Edge`] of the border.
+    pub left: Edge,
+
+    /// The [`BorderStyle`] of the border.
+    ///
+    /// The style applies to the whole border: every edge is dashed or dotted
+    /// the same way, only their widths and colors can differ.
+    pub style: BorderStyle,
+}
+
+impl Border {
+    /// Creates a solid [`Border`] with the same width and color on every
+    /// side.
+    pub fn new(width: f32, color: Color) -> Self {
+        let edge = Edge { width, color };
+
+        Border {
+            top: edge,
+            right: edge,
+            bottom: edge,
+            left: edge,
+            style: BorderStyle::Solid,
+        }
+    }
+
+    /// Sets the [`BorderStyle`] of the [`Border`].
+    pub fn style(self, style: BorderStyle) -> Self {
+        Border { style, ..self }
+    }
+
+    /// Returns the width and color of the [`Border`] if it is solid and the
+    /// same on every side.
+    pub fn uniform(&self) -> Option<(f32, Color)> {
+        let edges = [self.top, self.right, self.bottom, self.left];
+
+        if edges.iter().all(|edge| edge.width <= 0.0) {
+            return Some((0.0, Color::TRANSPARENT));
+        }
+
+        if self.style == BorderStyle::Solid && edges.iter().all(|edge| *edge == self.top) {
+            Some((self.top.width, self.top.color))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Border::new(0.0, Color::TRANSPARENT)
+    }
+}
+
+/// A side of a [`Border`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    /// The width of the edge, in logical pixels.
+    pub width: f32,
+
+    /// The color of the edge.
+    pub color: Color,
+}
+
+/// How the edges of a [`Border`] are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BorderStyle {
+    /// A continuous line.
+    Solid,
+
+    /// A series of dashes.
+    Dashed {
+        /// The length of each dash, in logical pixels.
+        dash: f32,
+
+        /// The space between dashes, in logical pixels.
+        gap: f32,
+    },
+
+    /// A series of round dots, as wide as the edge and spaced by their
+    /// diameter.
+    Dotted,
+}
+
+/// How the inside of a decorated quad is filled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fill {
+    /// A solid color.
+    Color(Color),
+
+    /// A [`Gradient`].
+    Gradient(Gradient),
+}
+
+impl From<Color> for Fill {
+    fn from(color: Color) -> Self {
+        Fill::Color(color)
+    }
+}
+
+impl From<Gradient> for Fill {
+    fn from(gradient: Gradient) -> Self {
+        Fill::Gradient(gradient)
+    }
+}
+
+/// A smooth transition between colors.
+///
+/// Colors are interpolated in linear space.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Gradient {
+    /// A gradient along a straight line through the center of the quad.
+    Linear {
+        /// The direction of the line, in radians.
+        ///
+        /// `0` goes from left to right and `PI / 2` from top to bottom.
+        angle: f32,
+
+        /// The colors of the gradient, sorted by offset.
+        stops: Vec<ColorStop>,
+    },
+
+    /// A gradient radiating from a point.
+    Radial {
+        /// The center of the gradient, relative to the bounds of the quad.
+        ///
+        /// `(0.0, 0.0)` is the top left corner and `(1.0, 1.0)` the bottom
+        /// right one.
+        center: Point,
+
+        /// The radius of the gradient, in logical pixels.
+        radius: f32,
+
+        /// The colors of the gradient, sorted by offset.
+        stops: Vec<ColorStop>,
+    },
+}
+
+impl Gradient {
+    /// Creates a [`Gradient::Linear`] with the given angle and stops.
+    pub fn linear(angle: f32, stops: Vec<ColorStop>) -> Self {
+        Gradient::Linear { angle, stops }
+    }
+
+    /// Creates a [`Gradient::Radial`] with the given center, radius and
+    /// stops.
+    pub fn radial(center: Point, radius: f32, stops: Vec<ColorStop>) -> Self {
+        Gradient::Radial {
+            center,
+            radius,
+            stops,
+        }
+    }
+
+    /// Returns the [`ColorStop`]s of the [`Gradient`].
+    pub fn stops(&self) -> &[ColorStop] {
+        match self {
+            Gradient::Linear { stops, .. } | Gradient::Radial { stops, .. } => stops,
+        }
+    }
+}
+
+/// A color at a specific position of a [`Gradient`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    /// The position of the stop, from `0.0` to `1.0`.
+    pub offset: f32,
+
+    /// The color of the stop.
+    pub color: Color,
+}
+
+impl ColorStop {
+    /// Creates a new [`ColorStop`].
+    pub fn new(offset: f32, color: Color) -> Self {
+        ColorStop { offset, color }
+    }
+}
+
+/// The shadow cast by a decorated quad.
+///
+/// The whole shadow is drawn under the quad, so it shows through translucent
+/// fills.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    /// The offset of the shadow from the quad, in logical pixels.
+    pub offset: Vector,
+
+    /// The radius of the blur of the shadow, in logical pixels.
+    ///
+    /// Like in CSS, the blur fades out over twice the standard deviation of
+    /// the gaussian it approximates.
+    pub blur_radius: f32,
+
+    /// How much the shadow grows in every direction before blurring, in
+    /// logical pixels. Negative values shrink it.
+    pub spread: f32,
+
+    /// The color of the shadow.
+    pub color: Color,
+}
+
+/// The extension of a [`Renderer`] drawing [`Decoration`]s.
+///
+/// [`Renderer`]: crate::Renderer
+pub trait Decorate {
+    /// Fills the given bounds with a [`Decoration`].
+    fn fill_decorated(&mut self, bounds: Rectangle, decoration: &Decoration);
+}
+
+impl Decorate for Renderer {
+    fn fill_decorated(&mut self, bounds: Rectangle, decoration: &Decoration) {
+        let solid = match (&decoration.fill, &decoration.shadow) {
+            (Fill::Color(color), None) => Some(*color),
+            _ => None,
+        };
+
+        if let (Some(color), Some(border_radius), Some((border_width, border_color))) = (
+            solid,
+            decoration.border_radius.uniform(),
+            decoration.border.uniform(),
+        ) {
+            self.fill_quad(
+                renderer::Quad {
+                    bounds,
+                    border_radius,
+                    border_width,
+                    border_color,
+                },
+                color,
+            );
+
+            return;
+        }
+
+        // Layers only know about solid quads, so we keep the decoration in
+        // the backend until the frame is rendered and tag the quad with it
+        let tag = match self.backend().register_decoration(decoration.clone()) {
+            Some(index) => Tag::Frame(index),
+            None => return,
+        };
+
+        self.fill_quad(
+            renderer::Quad {
+                bounds,
+                border_radius: 0.0,
+                border_width: tag.encode(),
+                border_color: Color::TRANSPARENT,
+            },
+            Color::TRANSPARENT,
+        );
+    }
+}
+
+/// A [`Decoration`] the style sheets of the built-in widgets can use.
+///
+/// Style sheets cannot reach the renderer, so a [`Handle`] stands for its
+/// decoration in the border width of a style instead. Widgets only draw a
+/// quad for a style with a background, but the decoration replaces the rest
+/// of the quad style, including the background itself:
+///
+/// ```no_run
+/// use iced_solstice::container;
+/// use iced_solstice::decoration::{Decoration, Handle, Shadow};
+/// # use iced_native::{Color, Vector};
+///
+/// struct Card(Handle);
+///
+/// impl container::StyleSheet for Card {
+///     fn style(&self) -> container::Style {
+///         container::Style {
+///             background: Some(Color::TRANSPARENT.into()),
+///             border_width: self.0.border_width(),
+///             ..container::Style::default()
+///         }
+///     }
+/// }
+///
+/// let card = Card(Handle::new(Decoration {
+///     fill: Color::WHITE.into(),
+///     border_radius: 8.0.into(),
+///     shadow: Some(Shadow {
+///         offset: Vector::new(0.0, 2.0),
+///         blur_radius: 8.0,
+///         spread: 0.0,
+///         color: Color::from_rgba(0.0, 0.0, 0.0, 0.3),
+///     }),
+///     ..Decoration::default()
+/// }));
+/// ```
+///
+/// The decoration stays registered until every clone of its [`Handle`] is
+/// dropped, so handles are meant to be created once and kept, like fonts.
+#[derive(Debug, Clone)]
+pub struct Handle {
+    id: u32,
+    decoration: Arc<Decoration>,
+}
+
+/// The decorations of the live [`Handle`]s, by id.
+static SHARED: Mutex<Vec<(u32, Weak<Decoration>)>> = Mutex::new(Vec::new());
+
+impl Handle {
+    /// Creates a [`Handle`] for the given [`Decoration`].
+    pub fn new(decoration: Decoration) -> Self {
+        warn_about_stops(&decoration);
+
+        let decoration = Arc::new(decoration);
+        let mut shared = SHARED.lock().expect("Lock shared decorations");
+
+        shared.retain(|(_, decoration)| decoration.strong_count() > 0);
+
+        // Ids of dropped handles are reused, so they stay within the tag
+        let id = (0..)
+            .find(|id| shared.iter().all(|(used, _)| used != id))
+            .expect("Free decoration id");
+
+        shared.push((id, Arc::downgrade(&decoration)));
+
+        Handle { id, decoration }
+    }
+
+    /// Returns the [`Decoration`] of the [`Handle`].
+    pub fn decoration(&self) -> &Decoration {
+        &self.decoration
+    }
+
+    /// Returns the border width standing for the [`Decoration`] in a style.
+    pub fn border_width(&self) -> f32 {
+        Tag::Shared(self.id).encode()
+    }
+}
+
+/// The decorations a frame is rendered with.
+#[derive(Debug, Default)]
+pub(crate) struct Decorations {
+    /// The decorations drawn since the last frame was rendered.
+    pending: Vec<Decoration>,
+
+    /// The decorations of the last draw, kept until the next one so the
+    /// same primitives can be presented again.
+    frame: Vec<Decoration>,
+
+    /// The decorations of the live [`Handle`]s.
+    shared: Vec<(u32, Arc<Decoration>)>,
+}
+
+impl Decorations {
+    /// Stores a [`Decoration`] until the next frame and returns its index, or
+    /// `None` if the frame has run out of them.
+    pub fn register(&mut self, decoration: Decoration) -> Option<usize> {
+        if self.pending.len() > Tag::MAX_INDEX as usize {
+            log::warn!(
+                "Decoration dropped, a frame cannot draw more than {}",
+                Tag::MAX_INDEX as usize + 1
+            );
+
+            return None;
+        }
+
+        warn_about_stops(&decoration);
+        self.pending.push(decoration);
+
+        Some(self.pending.len() - 1)
+    }
+
+    /// Starts rendering a frame, replacing the decorations of the previous
+    /// draw with the ones registered since.
+    ///
+    /// A frame without new decorations presents the last draw again, so it
+    /// keeps its decorations.
+    pub fn start_frame(&mut self) {
+        if !self.pending.is_empty() {
+            self.frame = std::mem::take(&mut self.pending);
+        }
+
+        let shared = SHARED.lock().expect("Lock shared decorations");
+
+        self.shared = shared
+            .iter()
+            .filter_map(|(id, decoration)| Some((*id, decoration.upgrade()?)))
+            .collect();
+    }
+
+    /// Returns the [`Decoration`] a quad was tagged with, if any.
+    pub fn get(&self, quad: &layer::Quad) -> Option<&Decoration> {
+        match Tag::decode(quad.border_width)? {
+            Tag::Frame(index) => self.frame.get(index),
+            Tag::Shared(id) => self
+                .shared
+                .iter()
+                .find(|(shared, _)| *shared == id)
+                .map(|(_, decoration)| decoration.as_ref()),
+        }
+    }
+}
+
+/// The reference to a [`Decoration`] carried by a quad.
+///
+/// It is encoded in the border width of the quad as a NaN, which no regular
+/// quad uses, with the reference in its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    /// A decoration drawn with [`Decorate::fill_decorated`], by index.
+    Frame(usize),
+
+    /// The decoration of a [`Handle`], by id.
+    Shared(u32),
+}
+
+impl Tag {
+    /// The bits of a quiet NaN, marking a tag.
+    const NAN: u32 = 0x7FC0_0000;
+
+    /// The payload bit telling shared decorations apart.
+    const SHARED: u32 = 1 << 21;
+
+    const MAX_INDEX: u32 = Self::SHARED - 1;
+
+    fn encode(self) -> f32 {
+        let payload = match self {
+            Tag::Frame(index) => index as u32,
+            Tag::Shared(id) => Self::SHARED | id,
+        };
+
+        f32::from_bits(Self::NAN | (payload & (Self::SHARED | Self::MAX_INDEX)))
+    }
+
+    fn decode(border_width: f32) -> Option<Self> {
+        let bits = border_width.to_bits();
+
+        if bits & !(Self::SHARED | Self::MAX_INDEX) != Self::NAN {
+            return None;
+        }
+
+        let index = bits & Self::MAX_INDEX;
+
+        Some(if bits & Self::SHARED == 0 {
+            Tag::Frame(index as usize)
+        } else {
+            Tag::Shared(index)
+        })
+    }
+}
+
+/// Warns, once, that the stops of a [`Gradient`] past [`MAX_STOPS`] are
+/// ignored.
+fn warn_about_stops(decoration: &Decoration) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    if let Fill::Gradient(gradient) = &decoration.fill {
+        if gradient.stops().len() > MAX_STOPS && !WARNED.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "Gradient of {} stops drawn with its first {} only",
+                gradient.stops().len(),
+                MAX_STOPS
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_survive_the_border_width() {
+        for tag in [
+            Tag::Frame(0),
+            Tag::Frame(42),
+            Tag::Frame(Tag::MAX_INDEX as usize),
+            Tag::Shared(0),
+            Tag::Shared(42),
+            Tag::Shared(Tag::MAX_INDEX),
+        ] {
+            let border_width = tag.encode();
+
+            assert!(border_width.is_nan(), "{:?} is not encoded as a NaN", tag);
+            assert_eq!(Tag::decode(border_width), Some(tag));
+        }
+    }
+
+    #[test]
+    fn regular_border_widths_are_not_tags() {
+        for border_width in [0.0, -0.0, 1.0, 2.5, f32::MAX, f32::INFINITY] {
+            assert_eq!(Tag::decode(border_width), None);
+        }
+
+        assert_eq!(Tag::decode(-Tag::Frame(0).encode()), None);
+    }
+}
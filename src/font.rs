@@ -0,0 +1,277 @@
+//! Load the fonts installed on the system by family name.
+//!
+//! Fonts are looked up in a database of the local font directories (the
+//! fontconfig directories on Linux), scanned the first time a font is
+//! loaded. A [`Descriptor`] can be used as the default font of the
+//! [`Settings`] or turned into an [`iced_native::Font`] with [`load`]:
+//!
+//! ```no_run
+//! use iced_solstice::font::{self, Descriptor, Weight};
+//!
+//! # fn main() -> Result<(), font::Error> {
+//! let heading = font::load(Descriptor::new("Inter").weight(Weight::BOLD))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Settings`]: crate::Settings
+use once_cell::sync::Lazy;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+static DATABASE: Lazy<Mutex<Database>> = Lazy::new(|| {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+
+    log::debug!("Found {} system font faces", fonts.len());
+
+    Mutex::new(Database {
+        fonts,
+        loaded: HashMap::new(),
+        files: HashMap::new(),
+    })
+});
+
+/// The family, weight and style of a system font.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Descriptor {
+    /// The name of the family of the font, like `"DejaVu Sans"`.
+    pub family: Cow<'static, str>,
+
+    /// The [`Weight`] of the font.
+    pub weight: Weight,
+
+    /// The [`Style`] of the font.
+    pub style: Style,
+}
+
+impl Descriptor {
+    /// Creates a [`Descriptor`] of the regular face of the given family.
+    pub fn new(family: impl Into<Cow<'static, str>>) -> Self {
+        Descriptor {
+            family: family.into(),
+            weight: Weight::NORMAL,
+            style: Style::Normal,
+        }
+    }
+
+    /// Sets the [`Weight`] of the [`Descriptor`].
+    pub fn weight(self, weight: Weight) -> Self {
+        Descriptor { weight, ..self }
+    }
+
+    /// Sets the [`Style`] of the [`Descriptor`].
+    pub fn style(self, style: Style) -> Self {
+        Descriptor { style, ..self }
+    }
+}
+
+/// The weight of a font, from `100` to `900`.
+///
+/// When a family has no face of the exact weight, the closest one is picked
+/// like in CSS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Weight(pub u16);
+
+impl Weight {
+    /// Thin weight (100).
+    pub const THIN: Weight = Weight(100);
+
+    /// Extra light weight (200).
+    pub const EXTRA_LIGHT: Weight = Weight(200);
+
+    /// Light weight (300).
+    pub const LIGHT: Weight = Weight(300);
+
+    /// Normal weight (400).
+    pub const NORMAL: Weight = Weight(400);
+
+    /// Medium weight (500).
+    pub const MEDIUM: Weight = Weight(500);
+
+    /// Semibold weight (600).
+    pub const SEMIBOLD: Weight = Weight(600);
+
+    /// Bold weight (700).
+    pub const BOLD: Weight = Weight(700);
+
+    /// Extra bold weight (800).
+    pub const EXTRA_BOLD: Weight = Weight(800);
+
+    /// Black weight (900).
+    pub const BLACK: Weight = Weight(900);
+}
+
+/// The slant of a font.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Style {
+    /// An upright face.
+    Normal,
+
+    /// A cursive face.
+    Italic,
+
+    /// A slanted face.
+    Oblique,
+}
+
+/// An error loading a system font.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// No installed font belongs to the family.
+    FamilyNotFound {
+        /// The name of the family.
+        family: String,
+    },
+
+    /// The file of the font matching the family could not be read.
+    Unreadable {
+        /// The name of the family.
+        family: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::FamilyNotFound { family } => {
+                write!(f, "no installed font belongs to the family \"{}\"", family)
+            }
+            Error::Unreadable { family } => {
+                write!(f, "the font file of the family \"{}\" could not be read", family)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Loads the system font matching the given [`Descriptor`].
+///
+/// Fonts stay in memory until the application exits, so loading the same
+/// face again is cheap. The name of the font is the PostScript name of the
+/// face, which tells it apart from the other faces of a collection.
+pub fn load(descriptor: Descriptor) -> Result<iced_native::Font, Error> {
+    let face = load_face(&descriptor)?;
+
+    Ok(iced_native::Font::External {
+        name: face.name,
+        bytes: face.bytes,
+    })
+}
+
+/// A face loaded from the database.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Face {
+    pub name: &'static str,
+    /// The bytes of the whole file of the face, which may be a collection.
+    pub bytes: &'static [u8],
+    /// The index of the face in its file.
+    pub index: u32,
+}
+
+#[derive(Debug)]
+struct Database {
+    fonts: fontdb::Database,
+    /// The faces loaded so far.
+    loaded: HashMap<fontdb::ID, Face>,
+    /// The bytes of the font files loaded so far, shared by the faces of a
+    /// collection.
+    files: HashMap<PathBuf, &'static [u8]>,
+}
+
+/// Loads the face matching the given [`Descriptor`].
+pub(crate) fn load_face(descriptor: &Descriptor) -> Result<Face, Error> {
+    // The database stays consistent even if a thread panicked while loading
+    let mut database = DATABASE.lock().unwrap_or_else(|error| error.into_inner());
+
+    let id = database
+        .fonts
+        .query(&fontdb::Query {
+            families: &[fontdb::Family::Name(&descriptor.family)],
+            weight: fontdb::Weight(descriptor.weight.0),
+            stretch: fontdb::Stretch::Normal,
+            style: match descriptor.style {
+                Style::Normal => fontdb::Style::Normal,
+                Style::Italic => fontdb::Style::Italic,
+                Style::Oblique => fontdb::Style::Oblique,
+            },
+        })
+        .ok_or_else(|| Error::FamilyNotFound {
+            family: descriptor.family.to_string(),
+        })?;
+
+    if let Some(face) = database.loaded.get(&id) {
+        return Ok(*face);
+    }
+
+    let (name, path) = database
+        .fonts
+        .face(id)
+        .map(|face| {
+            let path = match &face.source {
+                fontdb::Source::File(path) => Some(path.clone()),
+                _ => None,
+            };
+
+            (face.post_script_name.clone(), path)
+        })
+        .unwrap_or_default();
+
+    let shared = path.as_ref().and_then(|path| database.files.get(path)).copied();
+
+    // External fonts of `iced_native` borrow their name and bytes forever
+    let (bytes, index) = match shared {
+        Some(bytes) => {
+            let index = database.fonts.face(id).map_or(0, |face| face.index);
+
+            (bytes, index)
+        }
+        None => {
+            let (bytes, index) = database
+                .fonts
+                .with_face_data(id, |data, index| (data.to_vec(), index))
+                .ok_or_else(|| Error::Unreadable {
+                    family: descriptor.family.to_string(),
+                })?;
+
+            let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+
+            if let Some(path) = path {
+                let _ = database.files.insert(path, bytes);
+            }
+
+            (bytes, index)
+        }
+    };
+
+    let face = Face {
+        name: Box::leak(name.into_boxed_str()),
+        bytes,
+        index,
+    };
+
+    let _ = database.loaded.insert(id, face);
+
+    Ok(face)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_families_are_not_found() {
+        let family = "iced_solstice Missing Family";
+
+        assert_eq!(
+            load(Descriptor::new(family)).err(),
+            Some(Error::FamilyNotFound {
+                family: family.to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Draw raster and vector images.
+pub(crate) mod atlas;
+
+#[cfg(feature = "image")]
+mod raster;
+
+#[cfg(feature = "svg")]
+mod vector;
+
+use crate::program;
+use crate::settings::ColorSpace;
+use crate::Transformation;
+use atlas::Atlas;
+use bytemuck::{Pod, Zeroable};
+use iced_graphics::layer;
+use iced_native::Rectangle;
+use solstice::{
+    canvas::Canvas,
+    mesh::VertexMesh,
+    shader::{DynamicShader, RawUniformValue, UniformLocation},
+    vertex::Vertex,
+};
+use std::cell::RefCell;
+
+const MAX_INSTANCES: usize = 1_000;
+
+#[derive(Copy, Clone, Debug, Vertex, Pod, Zeroable)]
+#[repr(C)]
+struct Position {
+    position: [f32; 2],
+}
+
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Instance {
+    position: [f32; 2],
+    size: [f32; 2],
+    texture_coordinates: [f32; 4],
+}
+
+impl solstice::vertex::Vertex for Instance {
+    fn build_bindings() -> &'static [solstice::vertex::VertexFormat] {
+        use solstice::vertex::{AttributeType, VertexFormat};
+        &[
+            VertexFormat {
+                name: "i_Pos",
+                offset: 0,
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Scale",
+                offset: std::mem::size_of::<[f32; 2]>(),
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_TexCoord",
+                offset: std::mem::size_of::<[f32; 4]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+        ]
+    }
+}
+
+#[derive(Debug)]
+pub struct Pipeline {
+    program: DynamicShader,
+    quad: VertexMesh<Position>,
+    instances: VertexMesh<Instance>,
+    transform_location: UniformLocation,
+    scale_location: UniformLocation,
+    atlas: Atlas,
+    #[cfg(feature = "image")]
+    raster_cache: RefCell<raster::Cache>,
+    #[cfg(feature = "svg")]
+    vector_cache: RefCell<vector::Cache>,
+}
+
+impl Pipeline {
+    pub fn new(gl: &mut solstice::Context, color_space: ColorSpace) -> Pipeline {
+        let program = {
+            const SRC: &str = include_str!("shader/image.glsl");
+            program::create(gl, SRC, SRC, color_space)
+        };
+
+        let transform_location = program
+            .get_uniform_by_name("u_Transform")
+            .unwrap()
+            .location
+            .clone();
+        let scale_location = program
+            .get_uniform_by_name("u_Scale")
+            .unwrap()
+            .location
+            .clone();
+        let atlas_location = program
+            .get_uniform_by_name("u_Atlas")
+            .unwrap()
+            .location
+            .clone();
+
+        gl.use_shader(Some(&program));
+        let matrix: [f32; 16] = Transformation::identity().into();
+        gl.set_uniform_by_location(&transform_location, &RawUniformValue::Mat4(matrix.into()));
+        gl.set_uniform_by_location(&scale_location, &RawUniformValue::Float(1.0));
+        gl.set_uniform_by_location(&atlas_location, &RawUniformValue::SignedInt(0));
+
+        let instances = VertexMesh::new(gl, MAX_INSTANCES).unwrap();
+        let quad = VertexMesh::with_data(
+            gl,
+            &[
+                Position { position: [0., 0.] },
+                Position { position: [0., 1.] },
+                Position { position: [1., 0.] },
+                Position { position: [1., 1.] },
+            ],
+        )
+        .unwrap();
+
+        Pipeline {
+            program,
+            quad,
+            instances,
+            transform_location,
+            scale_location,
+            atlas: Atlas::new(gl),
+            #[cfg(feature = "image")]
+            raster_cache: RefCell::new(raster::Cache::default()),
+            #[cfg(feature = "svg")]
+            vector_cache: RefCell::new(vector::Cache::default()),
+        }
+    }
+
+    #[cfg(feature = "image")]
+    pub fn dimensions(&self, handle: &iced_native::image::Handle) -> (u32, u32) {
+        let mut cache = self.raster_cache.borrow_mut();
+        let memory = cache.load(handle);
+
+        memory.dimensions()
+    }
+
+    #[cfg(feature = "svg")]
+    pub fn viewport_dimensions(&self, handle: &iced_native::svg::Handle) -> (u32, u32) {
+        let mut cache = self.vector_cache.borrow_mut();
+        let svg = cache.load(handle);
+
+        svg.viewport_dimensions()
+    }
+
+    pub fn draw(
+        &mut self,
+        gl: &mut solstice::Context,
+        target: Option<&Canvas>,
+        target_height: u32,
+        images: &[layer::Image],
+        transformation: Transformation,
+        scale: f32,
+        bounds: Rectangle<u32>,
+    ) {
+        let mut instances = Vec::with_capacity(images.len());
+
+        for image in images {
+            match image {
+                #[cfg(feature = "image")]
+                layer::Image::Raster { handle, bounds } => {
+                    let mut cache = self.raster_cache.borrow_mut();
+
+                    if let Some(entry) = cache.upload(handle, gl, &mut self.atlas) {
+                        instances.push((
+                            entry.page,
+                            Instance {
+                                position: [bounds.x, bounds.y],
+                                size: [bounds.width, bounds.height],
+                                texture_coordinates: entry.texture_coordinates(),
+                            },
+                        ));
+                    }
+                }
+                #[cfg(not(feature = "image"))]
+                layer::Image::Raster { .. } => {}
+
+                #[cfg(feature = "svg")]
+                layer::Image::Vector { handle, bounds } => {
+                    let mut cache = self.vector_cache.borrow_mut();
+
+                    if let Some(entry) = cache.upload(
+                        handle,
+                        [bounds.width, bounds.height],
+                        scale,
+                        gl,
+                        &mut self.atlas,
+                    ) {
+                        instances.push((
+                            entry.page,
+                            Instance {
+                                position: [bounds.x, bounds.y],
+                                size: [bounds.width, bounds.height],
+                                texture_coordinates: entry.texture_coordinates(),
+                            },
+                        ));
+                    }
+                }
+                #[cfg(not(feature = "svg"))]
+                layer::Image::Vector { .. } => {}
+            }
+        }
+
+        if instances.is_empty() {
+            return;
+        }
+
+        let scissor = solstice::viewport::Viewport::new(
+            bounds.x as i32,
+            target_height.saturating_sub(bounds.y + bounds.height) as i32,
+            bounds.width as i32,
+            bounds.height as i32,
+        );
+
+        gl.use_shader(Some(&self.program));
+
+        let matrix: [f32; 16] = transformation.into();
+        gl.set_uniform_by_location(
+            &self.transform_location,
+            &RawUniformValue::Mat4(matrix.into()),
+        );
+        gl.set_uniform_by_location(&self.scale_location, &RawUniformValue::Float(scale));
+
+        let Pipeline {
+            program: shader,
+            quad,
+            instances: buffer,
+            atlas,
+            ..
+        } = self;
+
+        // Images are drawn in order, in runs sharing an atlas page
+        atlas::draw_runs(gl, atlas, &instances, |gl, run| {
+            for chunk in run.chunks(MAX_INSTANCES) {
+                buffer.set_vertices(gl, chunk, 0);
+
+                use solstice::mesh::MeshAttacher;
+                let attached = quad.attach_with_step(&*buffer, 1);
+
+                let geometry = solstice::Geometry {
+                    mesh: attached,
+                    draw_range: 0..4,
+                    draw_mode: solstice::DrawMode::TriangleStrip,
+                    instance_count: chunk.len() as _,
+                };
+                solstice::Renderer::draw(
+                    gl,
+                    &*shader,
+                    &geometry,
+                    solstice::PipelineSettings {
+                        depth_state: None,
+                        blend_state: Some(program::blend_state()),
+                        scissor_state: Some(scissor),
+                        framebuffer: target,
+                        ..Default::default()
+                    },
+                );
+            }
+        });
+    }
+
+    /// Evicts the images that were not drawn since the last call.
+    pub fn trim_cache(&mut self) {
+        #[cfg(feature = "image")]
+        self.raster_cache.borrow_mut().trim(&mut self.atlas);
+
+        #[cfg(feature = "svg")]
+        self.vector_cache.borrow_mut().trim(&mut self.atlas);
+    }
+}
@@ -0,0 +1,398 @@
+use solstice::image::{Image, Settings};
+use solstice::texture::{FilterMode, Texture, TextureType, TextureUpdate, WrapMode};
+use solstice::PixelFormat;
+
+pub const SIZE: u32 = 2048;
+
+/// The transparent border kept around every entry, so linear filtering does
+/// not bleed the pixels of its neighbours into it.
+const PADDING: u32 = 1;
+
+/// A region of an [`Atlas`] page holding a single image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    /// The index of the page holding the entry.
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// The width and height of the page.
+    page_size: u32,
+}
+
+impl Entry {
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The normalized texture coordinates of the entry, as `[x, y, width, height]`.
+    pub fn texture_coordinates(&self) -> [f32; 4] {
+        let size = self.page_size as f32;
+
+        [
+            self.x as f32 / size,
+            self.y as f32 / size,
+            self.width as f32 / size,
+            self.height as f32 / size,
+        ]
+    }
+}
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    cursor: u32,
+    allocations: usize,
+}
+
+/// The horizontal shelves packing the entries of a square region.
+#[derive(Debug)]
+struct Shelves {
+    size: u32,
+    shelves: Vec<Shelf>,
+}
+
+/// A single RGBA texture packing images in horizontal shelves.
+#[derive(Debug)]
+struct Page {
+    texture: Image,
+    shelves: Shelves,
+}
+
+/// RGBA textures packing images in horizontal shelves.
+///
+/// A new page is added whenever the existing ones are full, and images
+/// larger than a page get a page of their own.
+#[derive(Debug)]
+pub struct Atlas {
+    pages: Vec<Option<Page>>,
+}
+
+impl Atlas {
+    pub fn new(gl: &mut solstice::Context) -> Self {
+        let page = Page::new(gl, SIZE).expect("Create image atlas");
+
+        Atlas {
+            pages: vec![Some(page)],
+        }
+    }
+
+    /// Returns the texture of the given page.
+    pub fn texture(&self, page: usize) -> &Image {
+        &self.pages[page].as_ref().expect("Atlas page").texture
+    }
+
+    /// Returns the amount of pages of the atlas.
+    pub fn pages(&self) -> usize {
+        self.pages.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.iter().flatten().all(|page| page.shelves.is_empty())
+    }
+
+    /// Uploads the given RGBA pixels into a free region of the atlas, adding
+    /// a page if none has enough room left.
+    ///
+    /// Returns `None` if a new page could not be created.
+    pub fn upload(
+        &mut self,
+        gl: &mut solstice::Context,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Option<Entry> {
+        let entry = match self.allocate(width, height) {
+            Some(entry) => entry,
+            None => {
+                let padded = width.max(height) + PADDING * 2;
+                let size = SIZE.max(padded);
+
+                if size > SIZE {
+                    log::debug!(
+                        "Image of {}x{} is larger than an atlas page, giving it its own texture",
+                        width,
+                        height
+                    );
+                }
+
+                let page = match Page::new(gl, size) {
+                    Ok(page) => page,
+                    Err(error) => {
+                        log::warn!(
+                            "Image of {}x{} dropped, a new atlas page failed to be created: {:?}",
+                            width,
+                            height,
+                            error
+                        );
+
+                        return None;
+                    }
+                };
+
+                let index = match self.pages.iter().position(Option::is_none) {
+                    Some(index) => {
+                        self.pages[index] = Some(page);
+                        index
+                    }
+                    None => {
+                        self.pages.push(Some(page));
+                        self.pages.len() - 1
+                    }
+                };
+
+                self.pages[index]
+                    .as_mut()
+                    .and_then(|page| page.allocate(index, width, height))?
+            }
+        };
+
+        // The padding is uploaded with the entry, as the region may still
+        // hold the pixels of a released one
+        let (padded_width, padded_height) = (width + PADDING * 2, height + PADDING * 2);
+        let mut padded = vec![0; (padded_width * padded_height * 4) as usize];
+
+        for (row, source) in pixels.chunks_exact((width.max(1) * 4) as usize).enumerate() {
+            let start = (((row as u32 + PADDING) * padded_width + PADDING) * 4) as usize;
+
+            padded[start..start + source.len()].copy_from_slice(source);
+        }
+
+        let texture = self.texture(entry.page);
+        let mut info = texture.get_texture_info();
+        info.set_width(padded_width);
+        info.set_height(padded_height);
+
+        gl.set_texture_sub_data(
+            texture.get_texture_key(),
+            info,
+            texture.get_texture_type(),
+            &padded,
+            entry.x - PADDING,
+            entry.y - PADDING,
+        );
+
+        Some(entry)
+    }
+
+    /// Releases the region of the given [`Entry`].
+    ///
+    /// Shelves are reclaimed once all of their entries have been released,
+    /// and pages once all of their shelves have, except for the first one.
+    pub fn remove(&mut self, entry: &Entry) {
+        let page = match self.pages.get_mut(entry.page) {
+            Some(Some(page)) => page,
+            _ => return,
+        };
+
+        page.remove(entry);
+
+        if entry.page > 0 && page.shelves.is_empty() {
+            self.pages[entry.page] = None;
+        }
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<Entry> {
+        self.pages
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, page)| Some((index, page.as_mut()?)))
+            .find_map(|(index, page)| page.allocate(index, width, height))
+    }
+}
+
+impl Page {
+    fn new(gl: &mut solstice::Context, size: u32) -> Result<Self, solstice::GraphicsError> {
+        let texture = Image::new(
+            gl,
+            TextureType::Tex2D,
+            PixelFormat::RGBA8,
+            size,
+            size,
+            Settings {
+                mipmaps: false,
+                filter: FilterMode::Linear.into(),
+                wrap: WrapMode::Clamp.into(),
+                ..Default::default()
+            },
+        )?;
+
+        Ok(Page {
+            texture,
+            shelves: Shelves::new(size),
+        })
+    }
+
+    fn remove(&mut self, entry: &Entry) {
+        self.shelves.remove(entry.y);
+    }
+
+    fn allocate(&mut self, page: usize, width: u32, height: u32) -> Option<Entry> {
+        let (x, y) = self.shelves.allocate(width, height)?;
+
+        Some(Entry {
+            page,
+            x,
+            y,
+            width,
+            height,
+            page_size: self.shelves.size,
+        })
+    }
+}
+
+impl Shelves {
+    fn new(size: u32) -> Self {
+        Shelves {
+            size,
+            shelves: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.shelves.is_empty()
+    }
+
+    /// Releases the entry placed at the given `y`.
+    fn remove(&mut self, y: u32) {
+        let y = y - PADDING;
+
+        if let Some(index) = self.shelves.iter().position(|shelf| shelf.y == y) {
+            let shelf = &mut self.shelves[index];
+            shelf.allocations -= 1;
+
+            if shelf.allocations == 0 {
+                shelf.cursor = 0;
+            }
+        }
+
+        // Empty shelves at the end give their room back to the page
+        while self
+            .shelves
+            .last()
+            .map_or(false, |shelf| shelf.allocations == 0)
+        {
+            let _ = self.shelves.pop();
+        }
+    }
+
+    /// Finds room for an entry of the given size, returning its position.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let size = self.size;
+        let (padded_width, padded_height) = (width + PADDING * 2, height + PADDING * 2);
+
+        if padded_width > size || padded_height > size {
+            return None;
+        }
+
+        // Pick the tightest shelf with enough room left
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= padded_height && size - shelf.cursor >= padded_width)
+            .min_by_key(|shelf| shelf.height - padded_height);
+
+        let shelf = match best {
+            Some(shelf) => shelf,
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map(|shelf| shelf.y + shelf.height)
+                    .unwrap_or(0);
+
+                if size - y < padded_height {
+                    return None;
+                }
+
+                self.shelves.push(Shelf {
+                    y,
+                    height: padded_height,
+                    cursor: 0,
+                    allocations: 0,
+                });
+
+                self.shelves.last_mut().unwrap()
+            }
+        };
+
+        let position = (shelf.cursor + PADDING, shelf.y + PADDING);
+
+        shelf.cursor += padded_width;
+        shelf.allocations += 1;
+
+        Some(position)
+    }
+}
+
+/// Draws instances in order, in runs sharing an atlas page.
+///
+/// The texture of the page of every run is bound to the first unit before
+/// `draw` is called with the instances of the run.
+pub fn draw_runs<T: Copy>(
+    gl: &mut solstice::Context,
+    atlas: &Atlas,
+    instances: &[(usize, T)],
+    mut draw: impl FnMut(&mut solstice::Context, &[T]),
+) {
+    let mut start = 0;
+
+    while start < instances.len() {
+        let page = instances[start].0;
+        let end = instances[start..]
+            .iter()
+            .position(|(other, _)| *other != page)
+            .map_or(instances.len(), |length| start + length);
+
+        let run: Vec<T> = instances[start..end]
+            .iter()
+            .map(|(_, instance)| *instance)
+            .collect();
+
+        let texture = atlas.texture(page);
+        gl.bind_texture_to_unit(
+            texture.get_texture_type(),
+            texture.get_texture_key(),
+            0.into(),
+        );
+
+        draw(gl, &run);
+
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shelves_are_reclaimed_out_of_order() {
+        let mut shelves = Shelves::new(SIZE);
+
+        let (_, a) = shelves.allocate(10, 10).unwrap();
+        let (_, b) = shelves.allocate(20, 20).unwrap();
+        let (_, c) = shelves.allocate(30, 30).unwrap();
+        assert_eq!(shelves.shelves.len(), 3);
+
+        shelves.remove(b);
+        shelves.remove(c);
+        assert_eq!(shelves.shelves.len(), 1);
+
+        shelves.remove(a);
+        assert!(shelves.is_empty());
+    }
+
+    #[test]
+    fn emptied_shelves_are_reused() {
+        let mut shelves = Shelves::new(SIZE);
+
+        let (_, a) = shelves.allocate(10, 10).unwrap();
+        let _ = shelves.allocate(20, 20).unwrap();
+
+        shelves.remove(a);
+        assert_eq!(shelves.allocate(10, 10), Some((PADDING, a)));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::image::atlas::{self, Atlas};
+use iced_native::svg;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug)]
+pub enum Svg {
+    Loaded(usvg::Tree),
+    NotFound,
+}
+
+impl Svg {
+    pub fn viewport_dimensions(&self) -> (u32, u32) {
+        match self {
+            Svg::Loaded(tree) => {
+                let size = tree.svg_node().size;
+
+                (size.width() as u32, size.height() as u32)
+            }
+            Svg::NotFound => (1, 1),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Cache {
+    svgs: HashMap<u64, Svg>,
+    rasterized: HashMap<(u64, u32, u32), atlas::Entry>,
+    svg_hits: HashSet<u64>,
+    rasterized_hits: HashSet<(u64, u32, u32)>,
+}
+
+impl Cache {
+    pub fn load(&mut self, handle: &svg::Handle) -> &Svg {
+        // Trees only used for layout must survive the next trim too
+        let _ = self.svg_hits.insert(handle.id());
+
+        if self.svgs.contains_key(&handle.id()) {
+            return self.svgs.get(&handle.id()).unwrap();
+        }
+
+        let opt = usvg::Options::default();
+
+        let svg = match handle.data() {
+            svg::Data::Path(path) => match std::fs::read(path) {
+                Ok(data) => match usvg::Tree::from_data(&data, &opt) {
+                    Ok(tree) => Svg::Loaded(tree),
+                    Err(_) => Svg::NotFound,
+                },
+                Err(_) => Svg::NotFound,
+            },
+            svg::Data::Bytes(bytes) => match usvg::Tree::from_data(&bytes, &opt) {
+                Ok(tree) => Svg::Loaded(tree),
+                Err(_) => Svg::NotFound,
+            },
+        };
+
+        let _ = self.svgs.insert(handle.id(), svg);
+        self.svgs.get(&handle.id()).unwrap()
+    }
+
+    /// Rasterizes the SVG at the given physical size and uploads it to the
+    /// [`Atlas`], reusing a previous rasterization of the same size.
+    pub fn upload(
+        &mut self,
+        handle: &svg::Handle,
+        [width, height]: [f32; 2],
+        scale: f32,
+        gl: &mut solstice::Context,
+        atlas: &mut Atlas,
+    ) -> Option<&atlas::Entry> {
+        let id = handle.id();
+
+        let (width, height) = (
+            (scale * width).ceil() as u32,
+            (scale * height).ceil() as u32,
+        );
+
+        if self.rasterized.contains_key(&(id, width, height)) {
+            let _ = self.svg_hits.insert(id);
+            let _ = self.rasterized_hits.insert((id, width, height));
+
+            return self.rasterized.get(&(id, width, height));
+        }
+
+        match self.load(handle) {
+            Svg::Loaded(tree) => {
+                if width == 0 || height == 0 {
+                    return None;
+                }
+
+                let mut img = tiny_skia::Pixmap::new(width, height)?;
+
+                resvg::render(
+                    tree,
+                    if width > height {
+                        usvg::FitTo::Width(width)
+                    } else {
+                        usvg::FitTo::Height(height)
+                    },
+                    img.as_mut(),
+                )?;
+
+                let mut rgba = img.take();
+
+                // `tiny_skia` produces premultiplied alpha, while we blend
+                // with straight alpha.
+                for pixel in rgba.chunks_exact_mut(4) {
+                    let alpha = pixel[3];
+
+                    if alpha > 0 && alpha < 255 {
+                        for channel in &mut pixel[..3] {
+                            *channel = ((*channel as u32 * 255) / alpha as u32) as u8;
+                        }
+                    }
+                }
+
+                let entry = atlas.upload(gl, width, height, &rgba)?;
+
+                let _ = self.rasterized_hits.insert((id, width, height));
+                let _ = self.rasterized.insert((id, width, height), entry);
+
+                self.rasterized.get(&(id, width, height))
+            }
+            Svg::NotFound => None,
+        }
+    }
+
+    pub fn trim(&mut self, atlas: &mut Atlas) {
+        let svg_hits = &self.svg_hits;
+        let rasterized_hits = &self.rasterized_hits;
+
+        self.svgs.retain(|k, _| svg_hits.contains(k));
+        self.rasterized.retain(|k, entry| {
+            let retain = rasterized_hits.contains(k);
+
+            if !retain {
+                atlas.remove(entry);
+            }
+
+            retain
+        });
+        self.svg_hits.clear();
+        self.rasterized_hits.clear();
+    }
+}
@@ -11,16 +11,49 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 mod backend;
+mod overlay;
 mod program;
 mod quad;
 mod text;
 mod triangle;
 
+#[cfg(any(feature = "image", feature = "svg"))]
+mod image;
+
+pub mod decoration;
 pub mod settings;
+pub mod widget;
+pub mod window;
+
+#[cfg(feature = "system_fonts")]
+#[cfg_attr(docsrs, doc(cfg(feature = "system_fonts")))]
+pub mod font;
+
+#[cfg(feature = "application")]
+#[cfg_attr(docsrs, doc(cfg(feature = "application")))]
+pub mod application;
+
+#[cfg(feature = "application")]
+#[cfg_attr(docsrs, doc(cfg(feature = "application")))]
+pub mod sandbox;
+
+/// Convert `winit` events into `iced_native` ones, to feed the events of a
+/// window you manage yourself to an [`Overlay`].
+#[cfg(feature = "winit")]
+#[cfg_attr(docsrs, doc(cfg(feature = "winit")))]
+pub use iced_winit::conversion;
 
-pub use backend::Backend;
+#[cfg(feature = "sdl2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sdl2")))]
+pub mod sdl;
+
+pub use backend::{Backend, Snapshot};
+pub use overlay::Overlay;
 pub use settings::Settings;
 
+#[doc(no_inline)]
+pub use widget::*;
+
 pub(crate) use iced_graphics::Transformation;
 
 pub use iced_graphics::{Error, Viewport};
@@ -34,3 +67,8 @@ pub use iced_native::{
 /// [`glow`]: https://github.com/grovesNL/glow
 /// [`iced`]: https://github.com/hecrj/iced
 pub type Renderer = iced_graphics::Renderer<Backend>;
+
+/// A generic widget.
+///
+/// This is an alias of an `iced_native` element with a default `Renderer`.
+pub type Element<'a, Message> = iced_native::Element<'a, Message, Renderer>;
@@ -0,0 +1,196 @@
+use crate::settings::ColorSpace;
+use crate::{Backend, Renderer, Settings, Viewport};
+use iced_native::clipboard::{self, Clipboard};
+use iced_native::program::{self, Program};
+use iced_native::{mouse, Command, Debug, Event, Point, Size};
+
+/// An iced user interface drawn on top of an existing `solstice` frame.
+///
+/// An [`Overlay`] owns the state of a [`Program`] together with everything
+/// needed to render it, so it can be embedded in a game loop you control:
+/// queue the events of the frame, call [`Overlay::update`], and then
+/// [`Overlay::draw`] once your own scene has been rendered.
+///
+/// The [`Overlay`] does not touch the global GL state of your context, so
+/// call [`window::configure`] with its [`Settings`] before drawing it.
+/// Unless your framebuffer is sRGB-encoded, use [`ColorSpace::LegacyGamma`]
+/// like [`Overlay::with_default_settings`] does.
+///
+/// [`window::configure`]: crate::window::configure
+/// [`ColorSpace::LegacyGamma`]: crate::settings::ColorSpace::LegacyGamma
+pub struct Overlay<P>
+where
+    P: Program<Renderer = Renderer> + 'static,
+{
+    state: program::State<P>,
+    renderer: Renderer,
+    viewport: Viewport,
+    cursor_position: Point,
+    clipboard: Box<dyn Clipboard>,
+    debug: Debug,
+}
+
+impl<P> Overlay<P>
+where
+    P: Program<Renderer = Renderer> + 'static,
+{
+    /// Creates a new [`Overlay`] for the given [`Program`] and [`Viewport`]
+    /// with the default [`Settings`] of an overlay.
+    ///
+    /// Most host framebuffers are not sRGB-encoded, so colors are blended
+    /// with [`ColorSpace::LegacyGamma`].
+    ///
+    /// [`ColorSpace::LegacyGamma`]: crate::settings::ColorSpace::LegacyGamma
+    pub fn with_default_settings(
+        gl: &mut solstice::Context,
+        program: P,
+        viewport: Viewport,
+    ) -> Self {
+        Self::new(
+            gl,
+            program,
+            viewport,
+            Settings {
+                color_space: ColorSpace::LegacyGamma,
+                ..Settings::default()
+            },
+        )
+    }
+
+    /// Creates a new [`Overlay`] for the given [`Program`] and [`Viewport`].
+    pub fn new(
+        gl: &mut solstice::Context,
+        program: P,
+        viewport: Viewport,
+        settings: Settings,
+    ) -> Self {
+        let mut renderer = Renderer::new(Backend::new(gl, settings));
+        let mut debug = Debug::new();
+        let cursor_position = Point::new(-1.0, -1.0);
+
+        let state = program::State::new(
+            program,
+            viewport.logical_size(),
+            cursor_position,
+            &mut renderer,
+            &mut debug,
+        );
+
+        Overlay {
+            state,
+            renderer,
+            viewport,
+            cursor_position,
+            clipboard: Box::new(clipboard::Null),
+            debug,
+        }
+    }
+
+    /// Sets the [`Clipboard`] used by the widgets of the [`Overlay`].
+    ///
+    /// By default, a clipboard that ignores all operations is used.
+    pub fn with_clipboard(mut self, clipboard: impl Clipboard + 'static) -> Self {
+        self.clipboard = Box::new(clipboard);
+        self
+    }
+
+    /// Returns a reference to the [`Program`] of the [`Overlay`].
+    pub fn program(&self) -> &P {
+        self.state.program()
+    }
+
+    /// Returns the current [`Viewport`] of the [`Overlay`].
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    /// Returns the current [`mouse::Interaction`] of the user interface.
+    ///
+    /// Use it to update the cursor icon of your window after each
+    /// [`Overlay::update`].
+    pub fn mouse_interaction(&self) -> mouse::Interaction {
+        self.state.mouse_interaction()
+    }
+
+    /// Returns a mutable reference to the [`Debug`] tracker of the
+    /// [`Overlay`].
+    pub fn debug_mut(&mut self) -> &mut Debug {
+        &mut self.debug
+    }
+
+    /// Changes the [`Viewport`] of the [`Overlay`], usually after a window
+    /// resize or a scale factor change.
+    ///
+    /// The layout will be recomputed on the next [`Overlay::update`].
+    pub fn resize(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    /// Sets the position of the mouse cursor, in logical coordinates.
+    ///
+    /// Queued [`mouse::Event::CursorMoved`] events update it automatically.
+    pub fn set_cursor_position(&mut self, position: Point) {
+        self.cursor_position = position;
+    }
+
+    /// Queues an [`Event`] to be processed by the next [`Overlay::update`].
+    pub fn queue_event(&mut self, event: Event) {
+        if let Event::Mouse(mouse::Event::CursorMoved { position }) = event {
+            self.cursor_position = position;
+        }
+
+        self.state.queue_event(event);
+    }
+
+    /// Queues a message to be processed by the next [`Overlay::update`].
+    pub fn queue_message(&mut self, message: P::Message) {
+        self.state.queue_message(message);
+    }
+
+    /// Returns whether the event and message queues are empty.
+    pub fn is_queue_empty(&self) -> bool {
+        self.state.is_queue_empty()
+    }
+
+    /// Processes all the queued events and messages, rebuilding the user
+    /// interface if necessary.
+    ///
+    /// Returns the [`Command`] produced by the [`Program`], if any. Running
+    /// it is left to the caller.
+    pub fn update(&mut self) -> Option<Command<P::Message>> {
+        let bounds: Size = self.viewport.logical_size();
+
+        self.state.update(
+            bounds,
+            self.cursor_position,
+            &mut self.renderer,
+            self.clipboard.as_mut(),
+            &mut self.debug,
+        )
+    }
+
+    /// Draws the user interface into the default framebuffer.
+    ///
+    /// Call it after rendering your own scene so the user interface is drawn
+    /// on top of it.
+    pub fn draw(&mut self, gl: &mut solstice::Context) {
+        let viewport = &self.viewport;
+        let overlay = self.debug.overlay();
+
+        self.renderer.with_primitives(|backend, primitives| {
+            backend.present(gl, primitives, viewport, &overlay);
+        });
+    }
+}
+
+impl<P> std::fmt::Debug for Overlay<P>
+where
+    P: Program<Renderer = Renderer> + 'static,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Overlay")
+            .field("viewport", &self.viewport)
+            .field("cursor_position", &self.cursor_position)
+            .finish()
+    }
+}
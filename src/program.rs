@@ -1,6 +1,79 @@
+use crate::settings::ColorSpace;
 use solstice::shader::DynamicShader;
 
-pub fn create(gl: &mut solstice::Context, vertex: &str, fragment: &str) -> DynamicShader {
-    let (v, f) = DynamicShader::create_source(vertex, fragment);
+/// Helpers shared by every shader to convert colors between the linear space
+/// they are blended in and the encoding of the framebuffer.
+const PRELUDE: &str = r#"
+vec3 srgb_to_linear(vec3 color) {
+    return mix(
+        color / 12.92,
+        pow((color + 0.055) / 1.055, vec3(2.4)),
+        step(0.04045, color)
+    );
+}
+
+vec3 linear_to_srgb(vec3 color) {
+    return mix(
+        color * 12.92,
+        1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055,
+        step(0.0031308, color)
+    );
+}
+
+// Converts a texel of an sRGB-encoded texture into linear space.
+vec4 texel_to_linear(vec4 texel) {
+    return vec4(srgb_to_linear(texel.rgb), texel.a);
+}
+
+// Encodes a linear color for the framebuffer. An sRGB framebuffer encodes
+// colors on its own, while legacy gamma blending expects them encoded.
+vec4 to_framebuffer(vec4 color) {
+#ifdef LEGACY_GAMMA
+    return vec4(linear_to_srgb(color.rgb), color.a);
+#else
+    return color;
+#endif
+}
+"#;
+
+pub fn create(
+    gl: &mut solstice::Context,
+    vertex: &str,
+    fragment: &str,
+    color_space: ColorSpace,
+) -> DynamicShader {
+    let header = match color_space {
+        ColorSpace::Srgb => String::from(PRELUDE),
+        ColorSpace::LegacyGamma => format!("#define LEGACY_GAMMA\n{}", PRELUDE),
+    };
+
+    let vertex = format!("{}\n{}", header, vertex);
+    let fragment = format!("{}\n{}", header, fragment);
+
+    let (v, f) = DynamicShader::create_source(&vertex, &fragment);
     DynamicShader::new(gl, v.as_str(), f.as_str()).unwrap()
 }
+
+/// The blending every pipeline draws with: straight alpha over the contents
+/// of the framebuffer.
+pub fn blend_state() -> solstice::BlendState {
+    solstice::BlendState::new(
+        solstice::BlendEquation::Add,
+        solstice::BlendFactor::SrcAlpha,
+        solstice::BlendFactor::OneMinusSrcAlpha,
+    )
+}
+
+/// The blending of colors whose components are already multiplied by their
+/// alpha.
+///
+/// Blending into a transparent target with [`blend_state`] multiplies colors
+/// by their alpha once, and applies it to the alpha itself too, so offscreen
+/// targets are drawn and composited premultiplied instead.
+pub fn premultiplied_blend_state() -> solstice::BlendState {
+    solstice::BlendState::new(
+        solstice::BlendEquation::Add,
+        solstice::BlendFactor::One,
+        solstice::BlendFactor::OneMinusSrcAlpha,
+    )
+}
@@ -1,15 +1,22 @@
+use crate::decoration::{self, Decoration, Decorations};
 use crate::program;
+use crate::settings::ColorSpace;
 use crate::Transformation;
 use bytemuck::{Pod, Zeroable};
 use iced_graphics::layer;
 use iced_native::Rectangle;
 use solstice::{
+    canvas::Canvas,
     mesh::VertexMesh,
     shader::{DynamicShader, RawUniformValue, UniformLocation},
     vertex::Vertex,
 };
 
-const MAX_INSTANCES: usize = 100_000;
+const INITIAL_INSTANCES: usize = 256;
+
+/// The number of consecutive frames using at most a quarter of the instance
+/// buffer before it is shrunk.
+const SHRINK_AFTER_FRAMES: u32 = 300;
 
 #[derive(Copy, Clone, Debug, Vertex, Pod, Zeroable)]
 #[repr(C)]
@@ -19,9 +26,186 @@ struct Position {
 
 #[derive(Debug)]
 pub struct Pipeline {
-    program: DynamicShader,
     quad: VertexMesh<Position>,
-    instances: VertexMesh<Quad>,
+    solid: Batch<Quad>,
+    gradient: Batch<Gradient>,
+    shadow: Batch<Shadow>,
+    border: Batch<Border>,
+    gradients: Vec<Gradient>,
+    shadows: Vec<Shadow>,
+    borders: Vec<Border>,
+}
+
+impl Pipeline {
+    pub fn new(gl: &mut solstice::Context, color_space: ColorSpace) -> Pipeline {
+        const SRC: &str = include_str!("shader/quad.glsl");
+
+        let solid = Batch::new(gl, SRC, color_space);
+        let gradient = Batch::new(gl, &format!("#define GRADIENT\n{}", SRC), color_space);
+        let shadow = Batch::new(gl, include_str!("shader/shadow.glsl"), color_space);
+        let border = Batch::new(gl, include_str!("shader/border.glsl"), color_space);
+
+        let quad = VertexMesh::with_data(
+            gl,
+            &[
+                Position { position: [0., 0.] },
+                Position { position: [0., 1.] },
+                Position { position: [1., 0.] },
+                Position { position: [1., 1.] },
+            ],
+        )
+        .unwrap();
+
+        Pipeline {
+            quad,
+            solid,
+            gradient,
+            shadow,
+            border,
+            gradients: Vec::new(),
+            shadows: Vec::new(),
+            borders: Vec::new(),
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        gl: &mut solstice::Context,
+        target: Option<&Canvas>,
+        target_height: u32,
+        instances: &[layer::Quad],
+        decorations: &Decorations,
+        transformation: Transformation,
+        scale: f32,
+        bounds: Rectangle<u32>,
+    ) {
+        let scissor = solstice::viewport::Viewport::new(
+            bounds.x as i32,
+            target_height.saturating_sub(bounds.y + bounds.height) as i32,
+            bounds.width as i32,
+            bounds.height as i32,
+        );
+
+        let decoration = |quad: &layer::Quad| decorations.get(quad);
+
+        // We draw runs of solid and decorated quads in order, so they keep
+        // overlapping each other like the primitives they come from
+        let mut start = 0;
+
+        while start < instances.len() {
+            let decorated = decoration(&instances[start]).is_some();
+            let mut end = start + 1;
+
+            if decorated {
+                // A shadow must be drawn right before its quad and a styled
+                // border right after it, so they delimit runs
+                while end < instances.len()
+                    && decoration(&instances[end - 1])
+                        .map_or(false, |decoration| decoration.border.uniform().is_some())
+                    && decoration(&instances[end])
+                        .map_or(false, |decoration| decoration.shadow.is_none())
+                {
+                    end += 1;
+                }
+            } else {
+                while end < instances.len() && decoration(&instances[end]).is_none() {
+                    end += 1;
+                }
+            }
+
+            if decorated {
+                self.shadows.clear();
+                self.shadows
+                    .extend(instances[start..end].iter().filter_map(|quad| {
+                        let decoration = decoration(quad)?;
+
+                        Some(Shadow::new(quad, decoration, decoration.shadow.as_ref()?))
+                    }));
+
+                self.shadow.draw(
+                    gl,
+                    &self.quad,
+                    target,
+                    target_height,
+                    &self.shadows,
+                    transformation,
+                    scale,
+                    scissor,
+                );
+
+                self.gradients.clear();
+                self.gradients.extend(
+                    instances[start..end]
+                        .iter()
+                        .filter_map(|quad| Some(Gradient::new(quad, decoration(quad)?))),
+                );
+
+                self.gradient.draw(
+                    gl,
+                    &self.quad,
+                    target,
+                    target_height,
+                    &self.gradients,
+                    transformation,
+                    scale,
+                    scissor,
+                );
+
+                self.borders.clear();
+                self.borders.extend(
+                    instances[start..end]
+                        .iter()
+                        .filter_map(|quad| Border::new(quad, decoration(quad)?)),
+                );
+
+                self.border.draw(
+                    gl,
+                    &self.quad,
+                    target,
+                    target_height,
+                    &self.borders,
+                    transformation,
+                    scale,
+                    scissor,
+                );
+            } else {
+                self.solid.draw(
+                    gl,
+                    &self.quad,
+                    target,
+                    target_height,
+                    bytemuck::cast_slice(&instances[start..end]),
+                    transformation,
+                    scale,
+                    scissor,
+                );
+            }
+
+            start = end;
+        }
+    }
+
+    /// Lets the instance buffers shrink once a frame has been drawn.
+    ///
+    /// It must be called once per frame, after every layer has been drawn.
+    pub fn end_frame(&mut self, gl: &mut solstice::Context) {
+        self.solid.end_frame(gl);
+        self.gradient.end_frame(gl);
+        self.shadow.end_frame(gl);
+        self.border.end_frame(gl);
+    }
+}
+
+/// A shader variant drawing instances of `T`, together with its instance
+/// buffer.
+#[derive(Debug)]
+struct Batch<T: Vertex> {
+    program: DynamicShader,
+    instances: VertexMesh<T>,
+    capacity: usize,
+    /// The most instances drawn at once during the current frame.
+    frame_peak: usize,
+    underused_frames: u32,
     transform_location: UniformLocation,
     scale_location: UniformLocation,
     screen_height_location: UniformLocation,
@@ -30,12 +214,12 @@ pub struct Pipeline {
     current_target_height: u32,
 }
 
-impl Pipeline {
-    pub fn new(gl: &mut solstice::Context) -> Pipeline {
-        let program = {
-            const SRC: &str = include_str!("shader/quad.glsl");
-            program::create(gl, SRC, SRC)
-        };
+impl<T> Batch<T>
+where
+    T: Vertex + Pod + Default,
+{
+    fn new(gl: &mut solstice::Context, source: &str, color_space: ColorSpace) -> Self {
+        let program = program::create(gl, source, source, color_space);
 
         let transform_location = program
             .get_uniform_by_name("u_Transform")
@@ -59,22 +243,14 @@ impl Pipeline {
         gl.set_uniform_by_location(&scale_location, &RawUniformValue::Float(1.0));
         gl.set_uniform_by_location(&screen_height_location, &RawUniformValue::Float(0.));
 
-        let instances = VertexMesh::new(gl, MAX_INSTANCES).unwrap();
-        let quad = VertexMesh::with_data(
-            gl,
-            &[
-                Position { position: [0., 0.] },
-                Position { position: [0., 1.] },
-                Position { position: [1., 0.] },
-                Position { position: [1., 1.] },
-            ],
-        )
-        .unwrap();
+        let instances = VertexMesh::new(gl, INITIAL_INSTANCES).unwrap();
 
-        Pipeline {
+        Batch {
             program,
-            quad,
             instances,
+            capacity: INITIAL_INSTANCES,
+            frame_peak: 0,
+            underused_frames: 0,
             transform_location,
             scale_location,
             screen_height_location,
@@ -84,21 +260,20 @@ impl Pipeline {
         }
     }
 
-    pub fn draw(
+    fn draw(
         &mut self,
         gl: &mut solstice::Context,
+        quad: &VertexMesh<Position>,
+        target: Option<&Canvas>,
         target_height: u32,
-        instances: &[layer::Quad],
+        instances: &[T],
         transformation: Transformation,
         scale: f32,
-        bounds: Rectangle<u32>,
+        scissor: solstice::viewport::Viewport<i32>,
     ) {
-        let scissor = solstice::viewport::Viewport::new(
-            bounds.x as i32,
-            (target_height - (bounds.y + bounds.height)) as i32,
-            bounds.width as i32,
-            bounds.height as i32,
-        );
+        if instances.is_empty() {
+            return;
+        }
 
         gl.use_shader(Some(&self.program));
 
@@ -127,38 +302,79 @@ impl Pipeline {
             self.current_target_height = target_height;
         }
 
-        let mut i = 0;
-        let total = instances.len();
-
-        while i < total {
-            let end = (i + MAX_INSTANCES).min(total);
-            let amount = end - i;
-
-            self.instances
-                .set_vertices(gl, bytemuck::cast_slice(&instances[i..end]), 0);
-            use solstice::mesh::MeshAttacher;
-            let attached = self.quad.attach_with_step(&self.instances, 1);
-
-            let geometry = solstice::Geometry {
-                mesh: attached,
-                draw_range: 0..4,
-                draw_mode: solstice::DrawMode::TriangleStrip,
-                instance_count: amount as _,
-            };
-            solstice::Renderer::draw(
-                gl,
-                &self.program,
-                &geometry,
-                solstice::PipelineSettings {
-                    depth_state: None,
-                    scissor_state: Some(scissor),
-                    ..Default::default()
-                },
-            );
+        self.reserve(gl, instances.len());
+
+        self.instances.set_vertices(gl, instances, 0);
+        use solstice::mesh::MeshAttacher;
+        let attached = quad.attach_with_step(&self.instances, 1);
+
+        let geometry = solstice::Geometry {
+            mesh: attached,
+            draw_range: 0..4,
+            draw_mode: solstice::DrawMode::TriangleStrip,
+            instance_count: instances.len() as _,
+        };
+        solstice::Renderer::draw(
+            gl,
+            &self.program,
+            &geometry,
+            solstice::PipelineSettings {
+                depth_state: None,
+                blend_state: Some(program::blend_state()),
+                scissor_state: Some(scissor),
+                framebuffer: target,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Makes sure the instance buffer can hold `amount` quads, growing it
+    /// geometrically so huge layers can be drawn at once.
+    fn reserve(&mut self, gl: &mut solstice::Context, amount: usize) {
+        self.frame_peak = self.frame_peak.max(amount);
+
+        if amount <= self.capacity {
+            return;
+        }
+
+        let mut capacity = self.capacity;
+
+        while capacity < amount {
+            capacity *= 2;
+        }
 
-            i += MAX_INSTANCES;
+        self.resize(gl, capacity);
+    }
+
+    /// Shrinks the instance buffer back after a sustained period of frames
+    /// using at most a quarter of it.
+    fn end_frame(&mut self, gl: &mut solstice::Context) {
+        let peak = std::mem::take(&mut self.frame_peak);
+
+        if peak > self.capacity / 4 || self.capacity <= INITIAL_INSTANCES {
+            self.underused_frames = 0;
+
+            return;
+        }
+
+        self.underused_frames += 1;
+
+        if self.underused_frames >= SHRINK_AFTER_FRAMES {
+            self.resize(gl, (self.capacity / 2).max(INITIAL_INSTANCES));
         }
     }
+
+    fn resize(&mut self, gl: &mut solstice::Context, capacity: usize) {
+        log::debug!(
+            "Resizing quad instance buffer from {} to {}",
+            self.capacity,
+            capacity
+        );
+
+        self.instances = VertexMesh::new(gl, capacity).unwrap();
+        self.capacity = capacity;
+        self.underused_frames = 0;
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -218,6 +434,345 @@ impl solstice::vertex::Vertex for Quad {
     }
 }
 
+/// A quad filled with a gradient of up to [`decoration::MAX_STOPS`] colors.
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Gradient {
+    position: [f32; 2],
+    size: [f32; 2],
+    border_color: [f32; 4],
+    border_radius: [f32; 4],
+    border_width: f32,
+    /// `(cos, sin, 0, 0)` of the angle of a linear gradient, or
+    /// `(center.x, center.y, radius, 1)` of a radial one.
+    gradient: [f32; 4],
+    offsets: [f32; decoration::MAX_STOPS],
+    colors: [[f32; 4]; decoration::MAX_STOPS],
+}
+
+impl Gradient {
+    fn new(quad: &layer::Quad, decoration: &Decoration) -> Self {
+        let (gradient, stops) = match &decoration.fill {
+            decoration::Fill::Color(color) => (
+                [1.0, 0.0, 0.0, 0.0],
+                vec![decoration::ColorStop::new(0.0, *color)],
+            ),
+            decoration::Fill::Gradient(gradient) => {
+                let mut stops = gradient.stops().to_vec();
+                stops.sort_by(|a, b| {
+                    a.offset
+                        .partial_cmp(&b.offset)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+
+                let parameters = match gradient {
+                    decoration::Gradient::Linear { angle, .. } => {
+                        [angle.cos(), angle.sin(), 0.0, 0.0]
+                    }
+                    decoration::Gradient::Radial { center, radius, .. } => {
+                        [center.x, center.y, *radius, 1.0]
+                    }
+                };
+
+                (parameters, stops)
+            }
+        };
+
+        let mut offsets = [0.0; decoration::MAX_STOPS];
+        let mut colors = [[0.0; 4]; decoration::MAX_STOPS];
+
+        // Missing stops repeat the last one, so they do not change the result
+        for (i, (offset, color)) in offsets.iter_mut().zip(&mut colors).enumerate() {
+            if let Some(stop) = stops.get(i.min(stops.len().saturating_sub(1))) {
+                *offset = stop.offset;
+                *color = stop.color.into_linear();
+            }
+        }
+
+        // Styled borders are drawn separately by a `Border`
+        let (border_width, border_color) = decoration
+            .border
+            .uniform()
+            .unwrap_or((0.0, iced_native::Color::TRANSPARENT));
+
+        Gradient {
+            position: quad.position,
+            size: quad.size,
+            border_color: border_color.into_linear(),
+            border_radius: decoration.border_radius.into(),
+            border_width,
+            gradient,
+            offsets,
+            colors,
+        }
+    }
+}
+
+impl solstice::vertex::Vertex for Gradient {
+    fn build_bindings() -> &'static [solstice::vertex::VertexFormat] {
+        use solstice::vertex::{AttributeType, VertexFormat};
+        &[
+            VertexFormat {
+                name: "i_Pos",
+                offset: 0,
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Scale",
+                offset: std::mem::size_of::<[f32; 2]>(),
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BorderColor",
+                offset: std::mem::size_of::<[f32; 4]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BorderRadius",
+                offset: std::mem::size_of::<[f32; 8]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BorderWidth",
+                offset: std::mem::size_of::<[f32; 12]>(),
+                atype: AttributeType::F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Gradient",
+                offset: std::mem::size_of::<[f32; 13]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Offsets",
+                offset: std::mem::size_of::<[f32; 17]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Color0",
+                offset: std::mem::size_of::<[f32; 21]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Color1",
+                offset: std::mem::size_of::<[f32; 25]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Color2",
+                offset: std::mem::size_of::<[f32; 29]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Color3",
+                offset: std::mem::size_of::<[f32; 33]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+        ]
+    }
+}
+
+/// The shadow cast by a decorated quad.
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Shadow {
+    position: [f32; 2],
+    size: [f32; 2],
+    color: [f32; 4],
+    offset: [f32; 2],
+    border_radius: [f32; 4],
+    blur_radius: f32,
+    spread: f32,
+}
+
+impl Shadow {
+    fn new(quad: &layer::Quad, decoration: &Decoration, shadow: &decoration::Shadow) -> Self {
+        Shadow {
+            position: quad.position,
+            size: quad.size,
+            color: shadow.color.into_linear(),
+            offset: [shadow.offset.x, shadow.offset.y],
+            border_radius: decoration.border_radius.into(),
+            blur_radius: shadow.blur_radius,
+            spread: shadow.spread,
+        }
+    }
+}
+
+impl solstice::vertex::Vertex for Shadow {
+    fn build_bindings() -> &'static [solstice::vertex::VertexFormat] {
+        use solstice::vertex::{AttributeType, VertexFormat};
+        &[
+            VertexFormat {
+                name: "i_Pos",
+                offset: 0,
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Scale",
+                offset: std::mem::size_of::<[f32; 2]>(),
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Color",
+                offset: std::mem::size_of::<[f32; 4]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Offset",
+                offset: std::mem::size_of::<[f32; 8]>(),
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BorderRadius",
+                offset: std::mem::size_of::<[f32; 10]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BlurRadius",
+                offset: std::mem::size_of::<[f32; 14]>(),
+                atype: AttributeType::F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Spread",
+                offset: std::mem::size_of::<[f32; 15]>(),
+                atype: AttributeType::F32,
+                normalize: false,
+            },
+        ]
+    }
+}
+
+/// The border of a decorated quad with different edges or a dashed or dotted
+/// style.
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Border {
+    position: [f32; 2],
+    size: [f32; 2],
+    border_radius: [f32; 4],
+    /// The widths of the top, right, bottom and left edges.
+    widths: [f32; 4],
+    colors: [[f32; 4]; 4],
+    /// The style of the border, followed by the length of dashes and gaps.
+    style: [f32; 4],
+}
+
+impl Border {
+    fn new(quad: &layer::Quad, decoration: &Decoration) -> Option<Self> {
+        let border = &decoration.border;
+
+        if border.uniform().is_some() {
+            return None;
+        }
+
+        let edges = [border.top, border.right, border.bottom, border.left];
+
+        let style = match border.style {
+            decoration::BorderStyle::Solid => [0.0, 0.0, 0.0, 0.0],
+            decoration::BorderStyle::Dashed { dash, gap } => [1.0, dash, gap, 0.0],
+            decoration::BorderStyle::Dotted => [2.0, 0.0, 0.0, 0.0],
+        };
+
+        Some(Border {
+            position: quad.position,
+            size: quad.size,
+            border_radius: decoration.border_radius.into(),
+            widths: [
+                edges[0].width,
+                edges[1].width,
+                edges[2].width,
+                edges[3].width,
+            ],
+            colors: [
+                edges[0].color.into_linear(),
+                edges[1].color.into_linear(),
+                edges[2].color.into_linear(),
+                edges[3].color.into_linear(),
+            ],
+            style,
+        })
+    }
+}
+
+impl solstice::vertex::Vertex for Border {
+    fn build_bindings() -> &'static [solstice::vertex::VertexFormat] {
+        use solstice::vertex::{AttributeType, VertexFormat};
+        &[
+            VertexFormat {
+                name: "i_Pos",
+                offset: 0,
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Scale",
+                offset: std::mem::size_of::<[f32; 2]>(),
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BorderRadius",
+                offset: std::mem::size_of::<[f32; 4]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BorderWidths",
+                offset: std::mem::size_of::<[f32; 8]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_TopColor",
+                offset: std::mem::size_of::<[f32; 12]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_RightColor",
+                offset: std::mem::size_of::<[f32; 16]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BottomColor",
+                offset: std::mem::size_of::<[f32; 20]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_LeftColor",
+                offset: std::mem::size_of::<[f32; 24]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_BorderStyle",
+                offset: std::mem::size_of::<[f32; 28]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+        ]
+    }
+}
+
 // unsafe fn create_instance_buffer(
 //     gl: &mut solstice::Context,
 //     size: usize,
@@ -0,0 +1,110 @@
+//! Run simple iced applications without [`Command`]s or subscriptions.
+//!
+//! [`Command`]: crate::Command
+use crate::application::{self, Application, Error, Settings};
+use crate::{Color, Command, Element};
+
+/// A sandboxed application.
+///
+/// A [`Sandbox`] is an [`Application`] that cannot run any asynchronous
+/// actions or be initialized with some external flags.
+pub trait Sandbox {
+    /// The type of __messages__ your [`Sandbox`] will produce.
+    type Message: std::fmt::Debug + Send;
+
+    /// Initializes the [`Sandbox`].
+    fn new() -> Self;
+
+    /// Returns the current title of the [`Sandbox`].
+    ///
+    /// This title can be dynamic! The runtime will automatically update the
+    /// title of your application when necessary.
+    fn title(&self) -> String;
+
+    /// Handles a __message__ and updates the state of the [`Sandbox`].
+    fn update(&mut self, message: Self::Message);
+
+    /// Returns the widgets to display in the [`Sandbox`].
+    fn view(&mut self) -> Element<'_, Self::Message>;
+
+    /// Returns the background color of the [`Sandbox`].
+    ///
+    /// By default, it returns [`Color::WHITE`].
+    fn background_color(&self) -> Color {
+        Color::WHITE
+    }
+
+    /// Returns the scale factor of the [`Sandbox`].
+    ///
+    /// By default, it returns `1.0`.
+    fn scale_factor(&self) -> f64 {
+        1.0
+    }
+
+    /// Returns whether the [`Sandbox`] should be terminated.
+    ///
+    /// By default, it returns `false`.
+    fn should_exit(&self) -> bool {
+        false
+    }
+}
+
+/// Runs a [`Sandbox`] with the provided settings.
+///
+/// This function does not return until the window of the [`Sandbox`] is
+/// closed.
+pub fn run<S>(settings: Settings<()>) -> Result<(), Error>
+where
+    S: Sandbox + 'static,
+{
+    application::run::<Instance<S>>(settings)
+}
+
+/// Wraps a [`Sandbox`] so it can be run as an [`Application`].
+#[derive(Debug)]
+struct Instance<S>(S);
+
+impl<S> iced_native::Program for Instance<S>
+where
+    S: Sandbox,
+{
+    type Renderer = crate::Renderer;
+    type Message = S::Message;
+
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        self.0.update(message);
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<'_, Self::Message> {
+        self.0.view()
+    }
+}
+
+impl<S> Application for Instance<S>
+where
+    S: Sandbox,
+{
+    type Flags = ();
+
+    fn new(_flags: ()) -> (Self, Command<Self::Message>) {
+        (Instance(S::new()), Command::none())
+    }
+
+    fn title(&self) -> String {
+        self.0.title()
+    }
+
+    fn background_color(&self) -> Color {
+        self.0.background_color()
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.0.scale_factor()
+    }
+
+    fn should_exit(&self) -> bool {
+        self.0.should_exit()
+    }
+}
@@ -0,0 +1,350 @@
+//! Run iced programs in an SDL2 window.
+//!
+//! SDL2 creates the window and its OpenGL context, which then feeds the
+//! `solstice::Context` the [`Backend`] draws with.
+//!
+//! [`Backend`]: crate::Backend
+pub mod conversion;
+
+use crate::{Color, Overlay, Renderer, Viewport};
+use iced_futures::futures::FutureExt;
+use iced_futures::Executor;
+use iced_native::command;
+use iced_native::program::Program;
+use iced_native::{clipboard, window};
+use iced_native::{Command, Size};
+use sdl2_rs as sdl2;
+use std::sync::{mpsc, Arc, Mutex};
+
+/// The amount of messages produced by futures that can wait to be processed
+/// before the futures block.
+const MESSAGE_CAPACITY: usize = 64;
+
+/// The settings of an SDL2 window running an iced program.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The title of the window.
+    pub title: String,
+
+    /// The initial logical size of the window.
+    pub size: (u32, u32),
+
+    /// Whether the window can be resized.
+    pub resizable: bool,
+
+    /// The color used to clear the window before drawing.
+    pub background_color: Color,
+
+    /// The settings of the renderer.
+    pub renderer: crate::Settings,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            title: String::from("iced_solstice"),
+            size: (1024, 768),
+            resizable: true,
+            background_color: Color::WHITE,
+            renderer: crate::Settings::default(),
+        }
+    }
+}
+
+/// An error that occurred while running an SDL2 window.
+#[derive(Debug)]
+pub enum Error {
+    /// SDL2 failed to initialize one of its subsystems.
+    Sdl(String),
+
+    /// The window could not be created.
+    WindowCreationFailed(sdl2::video::WindowBuildError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Sdl(error) => write!(f, "SDL2 error: {}", error),
+            Error::WindowCreationFailed(error) => {
+                write!(f, "the window could not be created: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<String> for Error {
+    fn from(error: String) -> Self {
+        Error::Sdl(error)
+    }
+}
+
+/// Runs a [`Program`] in a new SDL2 window.
+///
+/// This function does not return until the window is closed. The futures of
+/// the [`Command`]s produced by the [`Program`] run on a thread pool.
+pub fn run<P>(program: P, settings: Settings) -> Result<(), Error>
+where
+    P: Program<Renderer = Renderer> + 'static,
+    P::Message: 'static,
+{
+    let sdl = sdl2::init()?;
+    let video = sdl.video()?;
+
+    let gl_attr = video.gl_attr();
+    gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
+    gl_attr.set_context_version(3, 3);
+    gl_attr.set_framebuffer_srgb_compatible(
+        settings.renderer.color_space == crate::settings::ColorSpace::Srgb,
+    );
+
+    if let Some(antialiasing) = settings.renderer.antialiasing {
+        gl_attr.set_multisample_buffers(1);
+        gl_attr.set_multisample_samples(antialiasing.sample_count() as u8);
+    }
+
+    let (width, height) = settings.size;
+    let mut window = video.window(&settings.title, width, height);
+    let _ = window.opengl().allow_highdpi().position_centered();
+
+    if settings.resizable {
+        let _ = window.resizable();
+    }
+
+    let mut window = window.build().map_err(Error::WindowCreationFailed)?;
+
+    let _gl_context = window.gl_create_context()?;
+    let gl = unsafe {
+        let gl = solstice::glow::Context::from_loader_function(|name| {
+            video.gl_get_proc_address(name) as *const _
+        });
+        crate::window::configure(&gl, &settings.renderer);
+
+        gl
+    };
+    let mut gl = solstice::Context::new(gl);
+
+    let mut viewport = viewport(&window);
+    let clipboard = video.clipboard();
+    let mut overlay = Overlay::new(&mut gl, program, viewport.clone(), settings.renderer.clone())
+        .with_clipboard(Clipboard(clipboard.clone()));
+
+    let executor = iced_futures::executor::ThreadPool::new()
+        .map_err(|error| Error::Sdl(error.to_string()))?;
+    let (sender, receiver) = mpsc::sync_channel(MESSAGE_CAPACITY);
+
+    let events = sdl.event()?;
+    let proxy = Proxy {
+        sender,
+        waker: Arc::new(Mutex::new(events.event_sender())),
+        // SAFETY: The event type is only ever pushed with null data
+        wake_event: unsafe { events.register_event()? },
+    };
+
+    let mut event_pump = sdl.event_pump()?;
+    let mut cursor = None;
+    let mut redraw = true;
+
+    video.text_input().start();
+
+    'running: loop {
+        if redraw {
+            let [red, green, blue, alpha] = settings
+                .renderer
+                .color_space
+                .clear_color(settings.background_color);
+            solstice::Renderer::clear(
+                &mut gl,
+                solstice::ClearSettings {
+                    color: Some(
+                        solstice::Color {
+                            red,
+                            green,
+                            blue,
+                            alpha,
+                        }
+                        .into(),
+                    ),
+                    ..Default::default()
+                },
+            );
+
+            overlay.draw(&mut gl);
+            window.gl_swap_window();
+
+            redraw = false;
+        }
+
+        // Block until something happens, then drain the rest of the queue.
+        // Futures push a wake event once their message has been sent.
+        let first = event_pump.wait_event();
+
+        for event in std::iter::once(first).chain(event_pump.poll_iter()) {
+            redraw = true;
+
+            match &event {
+                sdl2::event::Event::Quit { .. } => break 'running,
+                sdl2::event::Event::Window {
+                    win_event: sdl2::event::WindowEvent::SizeChanged(..),
+                    ..
+                } => {
+                    viewport = self::viewport(&window);
+
+                    let Size { width, height } = viewport.physical_size();
+                    gl.set_viewport(0, 0, width as i32, height as i32);
+
+                    overlay.resize(viewport.clone());
+                }
+                _ => {}
+            }
+
+            for event in conversion::events(&event, viewport.logical_size()) {
+                overlay.queue_event(event);
+            }
+        }
+
+        for message in receiver.try_iter() {
+            overlay.queue_message(message);
+        }
+
+        while !overlay.is_queue_empty() {
+            if let Some(command) = overlay.update() {
+                for message in run_command(command, &executor, &proxy, &mut window, &clipboard)
+                {
+                    overlay.queue_message(message);
+                }
+            }
+
+            let system_cursor = conversion::mouse_interaction(overlay.mouse_interaction());
+            if let Ok(new_cursor) = sdl2::mouse::Cursor::from_system(system_cursor) {
+                new_cursor.set();
+                cursor = Some(new_cursor);
+            }
+        }
+    }
+
+    drop(cursor);
+
+    Ok(())
+}
+
+fn viewport(window: &sdl2::video::Window) -> Viewport {
+    let (width, height) = window.size();
+    let (physical_width, physical_height) = window.drawable_size();
+
+    // Both axes share the same scale factor in practice, but a collapsed
+    // axis tells nothing about it
+    let scales: Vec<f64> = [(physical_width, width), (physical_height, height)]
+        .iter()
+        .filter(|(_, logical)| *logical > 0)
+        .map(|(physical, logical)| *physical as f64 / *logical as f64)
+        .collect();
+
+    let scale_factor = if scales.is_empty() {
+        1.0
+    } else {
+        scales.iter().sum::<f64>() / scales.len() as f64
+    };
+
+    Viewport::with_physical_size(Size::new(physical_width, physical_height), scale_factor)
+}
+
+/// Runs the actions of a [`Command`], returning the messages they produce
+/// right away.
+fn run_command<Message>(
+    command: Command<Message>,
+    executor: &iced_futures::executor::ThreadPool,
+    proxy: &Proxy<Message>,
+    window: &mut sdl2::video::Window,
+    clipboard: &sdl2::clipboard::ClipboardUtil,
+) -> Vec<Message>
+where
+    Message: Send + 'static,
+{
+    let mut messages = Vec::new();
+
+    for action in command.actions() {
+        match action {
+            command::Action::Future(future) => {
+                let proxy = proxy.clone();
+
+                executor.spawn(future.map(move |message| proxy.send(message)));
+            }
+            command::Action::Clipboard(action) => match action {
+                clipboard::Action::Read(tag) => {
+                    messages.push(tag(clipboard.clipboard_text().ok()));
+                }
+                clipboard::Action::Write(contents) => {
+                    if let Err(error) = clipboard.set_clipboard_text(&contents) {
+                        log::warn!("Clipboard write failed: {}", error);
+                    }
+                }
+            },
+            command::Action::Window(action) => match action {
+                window::Action::Resize { width, height } => {
+                    if let Err(error) = window.set_size(width, height) {
+                        log::warn!("Window resize failed: {}", error);
+                    }
+                }
+                window::Action::Move { x, y } => window.set_position(
+                    sdl2::video::WindowPos::Positioned(x),
+                    sdl2::video::WindowPos::Positioned(y),
+                ),
+            },
+        }
+    }
+
+    messages
+}
+
+/// Sends the messages of futures to the event loop and wakes it up.
+struct Proxy<Message> {
+    sender: mpsc::SyncSender<Message>,
+    waker: Arc<Mutex<sdl2::event::EventSender>>,
+    wake_event: u32,
+}
+
+impl<Message> Proxy<Message> {
+    /// Sends a message, blocking while the event loop is behind.
+    fn send(&self, message: Message) {
+        if self.sender.send(message).is_err() {
+            return;
+        }
+
+        let waker = self.waker.lock().unwrap_or_else(|error| error.into_inner());
+        let _ = waker.push_event(sdl2::event::Event::User {
+            timestamp: 0,
+            window_id: 0,
+            type_: self.wake_event,
+            code: 0,
+            data1: std::ptr::null_mut(),
+            data2: std::ptr::null_mut(),
+        });
+    }
+}
+
+impl<Message> Clone for Proxy<Message> {
+    fn clone(&self) -> Self {
+        Proxy {
+            sender: self.sender.clone(),
+            waker: Arc::clone(&self.waker),
+            wake_event: self.wake_event,
+        }
+    }
+}
+
+/// The system clipboard, as seen by the widgets of the program.
+struct Clipboard(sdl2::clipboard::ClipboardUtil);
+
+impl iced_native::clipboard::Clipboard for Clipboard {
+    fn read(&self) -> Option<String> {
+        self.0.clipboard_text().ok()
+    }
+
+    fn write(&mut self, contents: String) {
+        if let Err(error) = self.0.set_clipboard_text(&contents) {
+            log::warn!("Clipboard write failed: {}", error);
+        }
+    }
+}
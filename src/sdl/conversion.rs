@@ -0,0 +1,379 @@
+//! Convert [`sdl2`] types into [`iced_native`] types.
+//!
+//! [`sdl2`]: https://github.com/Rust-SDL2/rust-sdl2
+use iced_native::keyboard;
+use iced_native::mouse;
+use iced_native::touch;
+use iced_native::window;
+use iced_native::{Event, Point, Size};
+use sdl2_rs as sdl2;
+
+/// Converts an SDL2 event into iced events.
+///
+/// SDL2 reports mouse positions in window coordinates, which are logical
+/// coordinates when the window is created with high DPI support. Touch
+/// positions are normalized, so the logical size of the window is needed to
+/// convert them.
+///
+/// A single SDL2 event may produce multiple iced events, like a text input
+/// event carrying several characters.
+pub fn events(event: &sdl2::event::Event, logical_size: Size) -> Vec<Event> {
+    use sdl2::event::{Event as SdlEvent, WindowEvent};
+
+    match event {
+        SdlEvent::Window { win_event, .. } => match win_event {
+            WindowEvent::SizeChanged(width, height) => {
+                vec![Event::Window(window::Event::Resized {
+                    width: *width as u32,
+                    height: *height as u32,
+                })]
+            }
+            WindowEvent::Moved(x, y) => vec![Event::Window(window::Event::Moved { x: *x, y: *y })],
+            WindowEvent::Close => vec![Event::Window(window::Event::CloseRequested)],
+            WindowEvent::FocusGained => vec![Event::Window(window::Event::Focused)],
+            WindowEvent::FocusLost => vec![Event::Window(window::Event::Unfocused)],
+            WindowEvent::Enter => vec![Event::Mouse(mouse::Event::CursorEntered)],
+            WindowEvent::Leave => vec![Event::Mouse(mouse::Event::CursorLeft)],
+            _ => Vec::new(),
+        },
+        SdlEvent::MouseMotion { x, y, .. } => vec![Event::Mouse(mouse::Event::CursorMoved {
+            position: Point::new(*x as f32, *y as f32),
+        })],
+        SdlEvent::MouseButtonDown { mouse_btn, .. } => {
+            vec![Event::Mouse(mouse::Event::ButtonPressed(mouse_button(
+                *mouse_btn,
+            )))]
+        }
+        SdlEvent::MouseButtonUp { mouse_btn, .. } => {
+            vec![Event::Mouse(mouse::Event::ButtonReleased(mouse_button(
+                *mouse_btn,
+            )))]
+        }
+        SdlEvent::MouseWheel {
+            x, y, direction, ..
+        } => {
+            let sign = match direction {
+                sdl2::mouse::MouseWheelDirection::Flipped => -1.0,
+                _ => 1.0,
+            };
+
+            vec![Event::Mouse(mouse::Event::WheelScrolled {
+                delta: mouse::ScrollDelta::Lines {
+                    x: *x as f32 * sign,
+                    y: *y as f32 * sign,
+                },
+            })]
+        }
+        SdlEvent::KeyDown {
+            keycode: Some(keycode),
+            keymod,
+            ..
+        } => {
+            let modifiers = modifiers(*keymod);
+            let mut events = modifiers_changed(*keycode, modifiers);
+
+            if let Some(key_code) = key_code(*keycode) {
+                events.push(Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }));
+            }
+
+            events
+        }
+        SdlEvent::KeyUp {
+            keycode: Some(keycode),
+            keymod,
+            ..
+        } => {
+            let modifiers = modifiers(*keymod);
+            let mut events = modifiers_changed(*keycode, modifiers);
+
+            if let Some(key_code) = key_code(*keycode) {
+                events.push(Event::Keyboard(keyboard::Event::KeyReleased {
+                    key_code,
+                    modifiers,
+                }));
+            }
+
+            events
+        }
+        SdlEvent::TextInput { text, .. } => text
+            .chars()
+            .map(|c| Event::Keyboard(keyboard::Event::CharacterReceived(c)))
+            .collect(),
+        SdlEvent::FingerDown {
+            finger_id, x, y, ..
+        } => vec![Event::Touch(touch::Event::FingerPressed {
+            id: finger(*finger_id),
+            position: finger_position(*x, *y, logical_size),
+        })],
+        SdlEvent::FingerMotion {
+            finger_id, x, y, ..
+        } => vec![Event::Touch(touch::Event::FingerMoved {
+            id: finger(*finger_id),
+            position: finger_position(*x, *y, logical_size),
+        })],
+        SdlEvent::FingerUp {
+            finger_id, x, y, ..
+        } => vec![Event::Touch(touch::Event::FingerLifted {
+            id: finger(*finger_id),
+            position: finger_position(*x, *y, logical_size),
+        })],
+        SdlEvent::DropFile { filename, .. } => vec![Event::Window(window::Event::FileDropped(
+            filename.into(),
+        ))],
+        _ => Vec::new(),
+    }
+}
+
+/// Converts a [`mouse::Interaction`] into an SDL2 system cursor.
+pub fn mouse_interaction(interaction: mouse::Interaction) -> sdl2::mouse::SystemCursor {
+    use mouse::Interaction;
+    use sdl2::mouse::SystemCursor;
+
+    match interaction {
+        Interaction::Idle => SystemCursor::Arrow,
+        Interaction::Pointer => SystemCursor::Hand,
+        Interaction::Working => SystemCursor::WaitArrow,
+        // SDL2 has no grab cursors, so we pick the closest ones
+        Interaction::Grab => SystemCursor::Hand,
+        Interaction::Grabbing => SystemCursor::SizeAll,
+        Interaction::Crosshair => SystemCursor::Crosshair,
+        Interaction::Text => SystemCursor::IBeam,
+        Interaction::ResizingHorizontally => SystemCursor::SizeWE,
+        Interaction::ResizingVertically => SystemCursor::SizeNS,
+    }
+}
+
+/// Converts an SDL2 mouse button into an iced [`mouse::Button`].
+pub fn mouse_button(mouse_button: sdl2::mouse::MouseButton) -> mouse::Button {
+    use sdl2::mouse::MouseButton;
+
+    match mouse_button {
+        MouseButton::Left => mouse::Button::Left,
+        MouseButton::Right => mouse::Button::Right,
+        MouseButton::Middle => mouse::Button::Middle,
+        MouseButton::X1 => mouse::Button::Other(4),
+        MouseButton::X2 => mouse::Button::Other(5),
+        MouseButton::Unknown => mouse::Button::Other(0),
+    }
+}
+
+/// Converts SDL2 key modifiers into iced [`keyboard::Modifiers`].
+pub fn modifiers(modifiers: sdl2::keyboard::Mod) -> keyboard::Modifiers {
+    use sdl2::keyboard::Mod;
+
+    let mut result = keyboard::Modifiers::empty();
+
+    result.set(
+        keyboard::Modifiers::SHIFT,
+        modifiers.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+    );
+    result.set(
+        keyboard::Modifiers::CTRL,
+        modifiers.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+    );
+    result.set(
+        keyboard::Modifiers::ALT,
+        modifiers.intersects(Mod::LALTMOD | Mod::RALTMOD),
+    );
+    result.set(
+        keyboard::Modifiers::LOGO,
+        modifiers.intersects(Mod::LGUIMOD | Mod::RGUIMOD),
+    );
+
+    result
+}
+
+/// Converts an SDL2 key code into an iced [`keyboard::KeyCode`], if iced has
+/// an equivalent.
+pub fn key_code(keycode: sdl2::keyboard::Keycode) -> Option<keyboard::KeyCode> {
+    use keyboard::KeyCode;
+    use sdl2::keyboard::Keycode;
+
+    Some(match keycode {
+        Keycode::Backspace => KeyCode::Backspace,
+        Keycode::Tab => KeyCode::Tab,
+        Keycode::Return => KeyCode::Enter,
+        Keycode::Escape => KeyCode::Escape,
+        Keycode::Space => KeyCode::Space,
+        Keycode::Quote => KeyCode::Apostrophe,
+        Keycode::Asterisk => KeyCode::Asterisk,
+        Keycode::Plus => KeyCode::Plus,
+        Keycode::Comma => KeyCode::Comma,
+        Keycode::Minus => KeyCode::Minus,
+        Keycode::Period => KeyCode::Period,
+        Keycode::Slash => KeyCode::Slash,
+        Keycode::Num0 => KeyCode::Key0,
+        Keycode::Num1 => KeyCode::Key1,
+        Keycode::Num2 => KeyCode::Key2,
+        Keycode::Num3 => KeyCode::Key3,
+        Keycode::Num4 => KeyCode::Key4,
+        Keycode::Num5 => KeyCode::Key5,
+        Keycode::Num6 => KeyCode::Key6,
+        Keycode::Num7 => KeyCode::Key7,
+        Keycode::Num8 => KeyCode::Key8,
+        Keycode::Num9 => KeyCode::Key9,
+        Keycode::Colon => KeyCode::Colon,
+        Keycode::Semicolon => KeyCode::Semicolon,
+        Keycode::Equals => KeyCode::Equals,
+        Keycode::At => KeyCode::At,
+        Keycode::LeftBracket => KeyCode::LBracket,
+        Keycode::Backslash => KeyCode::Backslash,
+        Keycode::RightBracket => KeyCode::RBracket,
+        Keycode::Caret => KeyCode::Caret,
+        Keycode::Underscore => KeyCode::Underline,
+        Keycode::Backquote => KeyCode::Grave,
+        Keycode::A => KeyCode::A,
+        Keycode::B => KeyCode::B,
+        Keycode::C => KeyCode::C,
+        Keycode::D => KeyCode::D,
+        Keycode::E => KeyCode::E,
+        Keycode::F => KeyCode::F,
+        Keycode::G => KeyCode::G,
+        Keycode::H => KeyCode::H,
+        Keycode::I => KeyCode::I,
+        Keycode::J => KeyCode::J,
+        Keycode::K => KeyCode::K,
+        Keycode::L => KeyCode::L,
+        Keycode::M => KeyCode::M,
+        Keycode::N => KeyCode::N,
+        Keycode::O => KeyCode::O,
+        Keycode::P => KeyCode::P,
+        Keycode::Q => KeyCode::Q,
+        Keycode::R => KeyCode::R,
+        Keycode::S => KeyCode::S,
+        Keycode::T => KeyCode::T,
+        Keycode::U => KeyCode::U,
+        Keycode::V => KeyCode::V,
+        Keycode::W => KeyCode::W,
+        Keycode::X => KeyCode::X,
+        Keycode::Y => KeyCode::Y,
+        Keycode::Z => KeyCode::Z,
+        Keycode::Delete => KeyCode::Delete,
+        Keycode::CapsLock => KeyCode::Capital,
+        Keycode::F1 => KeyCode::F1,
+        Keycode::F2 => KeyCode::F2,
+        Keycode::F3 => KeyCode::F3,
+        Keycode::F4 => KeyCode::F4,
+        Keycode::F5 => KeyCode::F5,
+        Keycode::F6 => KeyCode::F6,
+        Keycode::F7 => KeyCode::F7,
+        Keycode::F8 => KeyCode::F8,
+        Keycode::F9 => KeyCode::F9,
+        Keycode::F10 => KeyCode::F10,
+        Keycode::F11 => KeyCode::F11,
+        Keycode::F12 => KeyCode::F12,
+        Keycode::F13 => KeyCode::F13,
+        Keycode::F14 => KeyCode::F14,
+        Keycode::F15 => KeyCode::F15,
+        Keycode::F16 => KeyCode::F16,
+        Keycode::F17 => KeyCode::F17,
+        Keycode::F18 => KeyCode::F18,
+        Keycode::F19 => KeyCode::F19,
+        Keycode::F20 => KeyCode::F20,
+        Keycode::F21 => KeyCode::F21,
+        Keycode::F22 => KeyCode::F22,
+        Keycode::F23 => KeyCode::F23,
+        Keycode::F24 => KeyCode::F24,
+        Keycode::PrintScreen => KeyCode::Snapshot,
+        Keycode::ScrollLock => KeyCode::Scroll,
+        Keycode::Pause => KeyCode::Pause,
+        Keycode::Insert => KeyCode::Insert,
+        Keycode::Home => KeyCode::Home,
+        Keycode::PageUp => KeyCode::PageUp,
+        Keycode::End => KeyCode::End,
+        Keycode::PageDown => KeyCode::PageDown,
+        Keycode::Right => KeyCode::Right,
+        Keycode::Left => KeyCode::Left,
+        Keycode::Down => KeyCode::Down,
+        Keycode::Up => KeyCode::Up,
+        Keycode::NumLockClear => KeyCode::Numlock,
+        Keycode::KpDivide => KeyCode::NumpadDivide,
+        Keycode::KpMultiply => KeyCode::NumpadMultiply,
+        Keycode::KpMinus => KeyCode::NumpadSubtract,
+        Keycode::KpPlus => KeyCode::NumpadAdd,
+        Keycode::KpEnter => KeyCode::NumpadEnter,
+        Keycode::Kp0 => KeyCode::Numpad0,
+        Keycode::Kp1 => KeyCode::Numpad1,
+        Keycode::Kp2 => KeyCode::Numpad2,
+        Keycode::Kp3 => KeyCode::Numpad3,
+        Keycode::Kp4 => KeyCode::Numpad4,
+        Keycode::Kp5 => KeyCode::Numpad5,
+        Keycode::Kp6 => KeyCode::Numpad6,
+        Keycode::Kp7 => KeyCode::Numpad7,
+        Keycode::Kp8 => KeyCode::Numpad8,
+        Keycode::Kp9 => KeyCode::Numpad9,
+        Keycode::KpPeriod => KeyCode::NumpadDecimal,
+        Keycode::KpEquals => KeyCode::NumpadEquals,
+        Keycode::KpComma => KeyCode::NumpadComma,
+        Keycode::Application => KeyCode::Apps,
+        Keycode::Power => KeyCode::Power,
+        Keycode::Mute => KeyCode::Mute,
+        Keycode::VolumeUp => KeyCode::VolumeUp,
+        Keycode::VolumeDown => KeyCode::VolumeDown,
+        Keycode::Stop => KeyCode::Stop,
+        Keycode::Cut => KeyCode::Cut,
+        Keycode::Copy => KeyCode::Copy,
+        Keycode::Paste => KeyCode::Paste,
+        Keycode::Sysreq => KeyCode::Sysrq,
+        Keycode::LCtrl => KeyCode::LControl,
+        Keycode::LShift => KeyCode::LShift,
+        Keycode::LAlt => KeyCode::LAlt,
+        Keycode::LGui => KeyCode::LWin,
+        Keycode::RCtrl => KeyCode::RControl,
+        Keycode::RShift => KeyCode::RShift,
+        Keycode::RAlt => KeyCode::RAlt,
+        Keycode::RGui => KeyCode::RWin,
+        Keycode::AudioNext => KeyCode::NextTrack,
+        Keycode::AudioPrev => KeyCode::PrevTrack,
+        Keycode::AudioStop => KeyCode::MediaStop,
+        Keycode::AudioPlay => KeyCode::PlayPause,
+        Keycode::AudioMute => KeyCode::Mute,
+        Keycode::MediaSelect => KeyCode::MediaSelect,
+        Keycode::Mail => KeyCode::Mail,
+        Keycode::Calculator => KeyCode::Calculator,
+        Keycode::Computer => KeyCode::MyComputer,
+        Keycode::AcSearch => KeyCode::WebSearch,
+        Keycode::AcHome => KeyCode::WebHome,
+        Keycode::AcBack => KeyCode::WebBack,
+        Keycode::AcForward => KeyCode::WebForward,
+        Keycode::AcStop => KeyCode::WebStop,
+        Keycode::AcRefresh => KeyCode::WebRefresh,
+        Keycode::AcBookmarks => KeyCode::WebFavorites,
+        Keycode::Sleep => KeyCode::Sleep,
+        _ => return None,
+    })
+}
+
+/// Returns the [`keyboard::Event::ModifiersChanged`] event of a modifier
+/// key, if the given key is one.
+///
+/// SDL2 updates the modifiers of a key event before reporting it, so they
+/// already include a pressed modifier and exclude a released one.
+fn modifiers_changed(keycode: sdl2::keyboard::Keycode, modifiers: keyboard::Modifiers) -> Vec<Event> {
+    use sdl2::keyboard::Keycode;
+
+    match keycode {
+        Keycode::LShift
+        | Keycode::RShift
+        | Keycode::LCtrl
+        | Keycode::RCtrl
+        | Keycode::LAlt
+        | Keycode::RAlt
+        | Keycode::LGui
+        | Keycode::RGui => vec![Event::Keyboard(keyboard::Event::ModifiersChanged(
+            modifiers,
+        ))],
+        _ => Vec::new(),
+    }
+}
+
+fn finger(id: i64) -> touch::Finger {
+    touch::Finger(id as u64)
+}
+
+fn finger_position(x: f32, y: f32, logical_size: Size) -> Point {
+    Point::new(x * logical_size.width, y * logical_size.height)
+}
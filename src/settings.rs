@@ -1,16 +1,29 @@
 //! Configure a renderer.
 pub use iced_graphics::Antialiasing;
 
+use iced_native::Color;
+
 /// The settings of a [`Backend`].
 ///
 /// [`Backend`]: crate::Backend
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Settings {
     /// The bytes of the font that will be used by default.
     ///
     /// If `None` is provided, a default system font will be chosen.
     pub default_font: Option<&'static [u8]>,
 
+    /// The system font that will be used by default when no
+    /// [`default_font`] bytes are provided.
+    ///
+    /// If the font fails to load, the error is logged and a default system
+    /// font is chosen instead.
+    ///
+    /// [`default_font`]: Self::default_font
+    #[cfg(feature = "system_fonts")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "system_fonts")))]
+    pub default_font_family: Option<crate::font::Descriptor>,
+
     /// The default size of text.
     ///
     /// By default, it will be set to 20.
@@ -18,14 +31,172 @@ pub struct Settings {
 
     /// The antialiasing strategy that will be used for triangle primitives.
     pub antialiasing: Option<Antialiasing>,
+
+    /// The color space colors are blended in.
+    ///
+    /// By default, it is set to [`ColorSpace::Srgb`].
+    pub color_space: ColorSpace,
+
+    /// The configuration of the cache of rasterized glyphs.
+    pub glyph_cache: GlyphCache,
+
+    /// The fonts glyphs missing from the font of some text are taken from.
+    pub font_fallback: FontFallback,
 }
 
 impl Default for Settings {
     fn default() -> Settings {
         Settings {
             default_font: None,
+            #[cfg(feature = "system_fonts")]
+            default_font_family: None,
             default_text_size: 20,
             antialiasing: None,
+            color_space: ColorSpace::Srgb,
+            glyph_cache: GlyphCache::default(),
+            font_fallback: FontFallback::default(),
+        }
+    }
+}
+
+/// The fonts glyphs missing from the font of some text are taken from.
+///
+/// Each glyph is rendered with the first font of its fallback chain that
+/// covers it: the font of the text, then its fonts in
+/// [`FontFallback::per_font`], and finally [`FontFallback::fonts`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FontFallback {
+    /// The bytes of the fonts tried, in order, for any text.
+    pub fonts: &'static [&'static [u8]],
+
+    /// The bytes of the fonts tried first for the text of specific fonts,
+    /// keyed by the `name` of an external [`Font`].
+    ///
+    /// [`Font`]: iced_native::Font
+    pub per_font: &'static [(&'static str, &'static [&'static [u8]])],
+}
+
+/// The configuration of the atlas glyphs are rasterized into and of the
+/// cache of text layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphCache {
+    /// The initial size of the glyph atlas, in pixels.
+    ///
+    /// By default, it is set to `(2048, 2048)`.
+    pub initial_size: (u32, u32),
+
+    /// The size past which the glyph atlas is reset, in pixels.
+    ///
+    /// This is not a hard limit: the atlas still grows as much as the text of
+    /// a frame needs. Once it has grown past this size, it is rebuilt with its
+    /// initial size after the frame, giving the memory back. Frames that keep
+    /// needing more rasterize their glyphs again every time, which logs a
+    /// warning. By default, it is set to `(4096, 4096)`.
+    pub max_size: (u32, u32),
+
+    /// Whether glyphs are rasterized on multiple threads.
+    ///
+    /// This speeds up frames showing a lot of new text at the cost of
+    /// spawning threads. By default, it is set to `false`.
+    pub multithread: bool,
+
+    /// The amount of subpixel offsets per pixel glyphs are rasterized at.
+    ///
+    /// Glyphs are drawn at their exact position, but reuse a rasterization
+    /// made for an offset up to `1 / subpixel_positions` pixels away. By
+    /// default, it is set to `4`.
+    pub subpixel_positions: u16,
+
+    /// The amount of sizes per pixel glyphs are rasterized at.
+    ///
+    /// Glyphs reuse a rasterization made for a size up to `1 / scale_steps`
+    /// pixels away. By default, it is set to `2`.
+    pub scale_steps: u16,
+
+    /// When text layouts are dropped from the cache.
+    pub trim: Trim,
+}
+
+impl Default for GlyphCache {
+    fn default() -> Self {
+        GlyphCache {
+            initial_size: (2048, 2048),
+            max_size: (4096, 4096),
+            multithread: false,
+            subpixel_positions: 4,
+            scale_steps: 2,
+            trim: Trim::default(),
+        }
+    }
+}
+
+/// When cached text layouts are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trim {
+    /// Drop the layouts that were not used during the given amount of user
+    /// interface layouts, which must be at least `1`.
+    ///
+    /// Higher values help text heavy applications that show the same text
+    /// again after a while, like scrolling lists.
+    Idle(u32),
+
+    /// Keep every layout.
+    ///
+    /// Memory grows with every distinct text shown, so this is only suitable
+    /// for user interfaces with a bounded amount of text.
+    Never,
+}
+
+impl Default for Trim {
+    fn default() -> Self {
+        Trim::Idle(1)
+    }
+}
+
+/// The color space used when blending colors into the framebuffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Blend colors in linear space and let an sRGB framebuffer encode them.
+    ///
+    /// This produces physically correct blending and antialiasing.
+    Srgb,
+
+    /// Blend sRGB-encoded colors directly in a framebuffer without sRGB
+    /// conversion.
+    ///
+    /// This matches the output of most legacy 2D toolkits and is needed when
+    /// the framebuffer does not support sRGB encoding.
+    LegacyGamma,
+}
+
+impl ColorSpace {
+    /// Returns the components of the given [`Color`] the framebuffer should
+    /// be cleared with.
+    pub fn clear_color(self, color: Color) -> [f32; 4] {
+        match self {
+            ColorSpace::Srgb => color.into_linear(),
+            ColorSpace::LegacyGamma => [color.r, color.g, color.b, color.a],
+        }
+    }
+
+    /// Encodes the given linear components for the framebuffer.
+    pub(crate) fn encode(self, [r, g, b, a]: [f32; 4]) -> [f32; 4] {
+        match self {
+            ColorSpace::Srgb => [r, g, b, a],
+            ColorSpace::LegacyGamma => [
+                linear_to_srgb(r),
+                linear_to_srgb(g),
+                linear_to_srgb(b),
+                a,
+            ],
         }
     }
 }
+
+fn linear_to_srgb(component: f32) -> f32 {
+    if component <= 0.0031308 {
+        component * 12.92
+    } else {
+        1.055 * component.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,21 @@
+varying vec2 v_TexCoord;
+
+#ifdef VERTEX
+attribute vec2 position;
+
+void main() {
+    v_TexCoord = position;
+
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+uniform sampler2D u_Texture;
+
+void main() {
+    // The texture holds premultiplied colors, already encoded for the
+    // framebuffer
+    fragColor = Texel(u_Texture, v_TexCoord);
+}
+#endif
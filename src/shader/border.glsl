@@ -0,0 +1,155 @@
+varying vec2 v_Pos;
+varying vec2 v_Scale;
+varying vec4 v_BorderRadius;
+varying vec4 v_BorderWidths;
+varying vec4 v_TopColor;
+varying vec4 v_RightColor;
+varying vec4 v_BottomColor;
+varying vec4 v_LeftColor;
+varying vec4 v_BorderStyle;
+
+#ifdef VERTEX
+uniform mat4 u_Transform;
+uniform float u_Scale;
+
+attribute vec2 position;
+attribute vec2 i_Pos;
+attribute vec2 i_Scale;
+// The radii of the top left, top right, bottom right and bottom left corners
+attribute vec4 i_BorderRadius;
+// The widths of the top, right, bottom and left edges
+attribute vec4 i_BorderWidths;
+attribute vec4 i_TopColor;
+attribute vec4 i_RightColor;
+attribute vec4 i_BottomColor;
+attribute vec4 i_LeftColor;
+// The style (solid, dashed or dotted), dash length and gap length
+attribute vec4 i_BorderStyle;
+
+void main() {
+    vec2 p_Pos = i_Pos * u_Scale;
+    vec2 p_Scale = i_Scale * u_Scale;
+
+    vec4 border_radius = min(
+        i_BorderRadius,
+        vec4(min(i_Scale.x, i_Scale.y) / 2.0)
+    );
+
+    mat4 i_Transform = mat4(
+        vec4(p_Scale.x + 1.0, 0.0, 0.0, 0.0),
+        vec4(0.0, p_Scale.y + 1.0, 0.0, 0.0),
+        vec4(0.0, 0.0, 1.0, 0.0),
+        vec4(p_Pos - vec2(0.5, 0.5), 0.0, 1.0)
+    );
+
+    v_Pos = p_Pos;
+    v_Scale = p_Scale;
+    v_BorderRadius = border_radius * u_Scale;
+    v_BorderWidths = i_BorderWidths * u_Scale;
+    v_TopColor = i_TopColor;
+    v_RightColor = i_RightColor;
+    v_BottomColor = i_BottomColor;
+    v_LeftColor = i_LeftColor;
+    v_BorderStyle = vec4(i_BorderStyle.x, i_BorderStyle.yz * u_Scale, 0.0);
+
+    gl_Position = u_Transform * i_Transform * vec4(position, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+uniform float u_ScreenHeight;
+
+// The signed distance from a point to a box with rounded corners, negative
+// inside of it: https://iquilezles.org/articles/distfunctions2d/
+float rounded_box_sdf(in vec2 frag_coord, in vec2 position, in vec2 size, vec4 radii)
+{
+    vec2 half_size = size * 0.5;
+    vec2 p = frag_coord - (position + half_size);
+
+    // Pick the radius of the corner of the quadrant the point is in
+    vec2 side_radii = p.x > 0.0 ? radii.yz : radii.xw;
+    float radius = p.y > 0.0 ? side_radii.y : side_radii.x;
+
+    vec2 q = abs(p) - half_size + radius;
+
+    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - radius;
+}
+
+void main() {
+    vec2 fragCoord = vec2(gl_FragCoord.x, u_ScreenHeight - gl_FragCoord.y);
+    vec4 widths = v_BorderWidths;
+
+    // The border covers the space between the quad and the quad inset by
+    // the width of each edge
+    vec2 inner_pos = v_Pos + vec2(widths.w, widths.x);
+    vec2 inner_size = max(v_Scale - vec2(widths.y + widths.w, widths.x + widths.z), vec2(0.0));
+    vec4 inner_radius = max(
+        v_BorderRadius - vec4(
+            max(widths.w, widths.x),
+            max(widths.x, widths.y),
+            max(widths.z, widths.y),
+            max(widths.z, widths.w)
+        ),
+        vec4(0.0)
+    );
+
+    float outer = rounded_box_sdf(fragCoord, v_Pos, v_Scale, v_BorderRadius);
+    float inner = rounded_box_sdf(fragCoord, inner_pos, inner_size, inner_radius);
+    float coverage = clamp(0.5 - outer, 0.0, 1.0) * clamp(0.5 + inner, 0.0, 1.0);
+
+    // The edge with the smallest distance relative to its width owns the
+    // fragment, which splits corners diagonally
+    vec2 local = fragCoord - v_Pos;
+    vec4 distances = vec4(local.y, v_Scale.x - local.x, v_Scale.y - local.y, local.x);
+    vec4 ratios = mix(
+        distances / max(widths, vec4(0.0001)),
+        vec4(1e9),
+        step(widths, vec4(0.0))
+    );
+
+    vec4 color;
+    float width;
+    float along;
+    float across;
+
+    if (ratios.x <= min(min(ratios.y, ratios.z), ratios.w)) {
+        color = v_TopColor;
+        width = widths.x;
+        along = local.x;
+        across = distances.x;
+    } else if (ratios.y <= min(ratios.z, ratios.w)) {
+        color = v_RightColor;
+        width = widths.y;
+        along = local.y;
+        across = distances.y;
+    } else if (ratios.z <= ratios.w) {
+        color = v_BottomColor;
+        width = widths.z;
+        along = local.x;
+        across = distances.z;
+    } else {
+        color = v_LeftColor;
+        width = widths.w;
+        along = local.y;
+        across = distances.w;
+    }
+
+    float style_alpha = 1.0;
+
+    if (v_BorderStyle.x > 1.5) {
+        // Dots as wide as the edge, spaced by their diameter
+        float offset = mod(along, width * 2.0) - width;
+        float dot_distance = length(vec2(offset, across - width * 0.5));
+
+        style_alpha = clamp(0.5 + width * 0.5 - dot_distance, 0.0, 1.0);
+    } else if (v_BorderStyle.x > 0.5) {
+        float dash = v_BorderStyle.y;
+        float period = max(dash + v_BorderStyle.z, 0.0001);
+        float offset = mod(along, period);
+
+        style_alpha = clamp(min(offset, dash - offset) + 0.5, 0.0, 1.0);
+    }
+
+    fragColor = to_framebuffer(vec4(color.rgb, color.a * coverage * style_alpha));
+}
+#endif
@@ -0,0 +1,31 @@
+varying vec2 v_TexCoord;
+varying float v_Alpha;
+
+#ifdef VERTEX
+uniform mat4 u_Transform;
+
+attribute vec2 position;
+// The position and size of the glyph, in physical pixels
+attribute vec2 i_Pos;
+attribute vec2 i_Scale;
+attribute vec4 i_TexCoord;
+// The opacity of the text
+attribute float i_Alpha;
+
+void main() {
+    v_TexCoord = i_TexCoord.xy + position * i_TexCoord.zw;
+    v_Alpha = i_Alpha;
+
+    gl_Position = u_Transform * vec4(i_Pos + position * i_Scale, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+uniform sampler2D u_Atlas;
+
+void main() {
+    vec4 color = texel_to_linear(Texel(u_Atlas, v_TexCoord));
+
+    fragColor = to_framebuffer(vec4(color.rgb, color.a * v_Alpha));
+}
+#endif
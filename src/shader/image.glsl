@@ -0,0 +1,28 @@
+varying vec2 v_TexCoord;
+
+#ifdef VERTEX
+uniform mat4 u_Transform;
+uniform float u_Scale;
+
+attribute vec2 position;
+attribute vec2 i_Pos;
+attribute vec2 i_Scale;
+attribute vec4 i_TexCoord;
+
+void main() {
+    vec2 p_Pos = i_Pos * u_Scale;
+    vec2 p_Scale = i_Scale * u_Scale;
+
+    v_TexCoord = i_TexCoord.xy + position * i_TexCoord.zw;
+
+    gl_Position = u_Transform * vec4(p_Pos + position * p_Scale, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+uniform sampler2D u_Atlas;
+
+void main() {
+    fragColor = to_framebuffer(texel_to_linear(Texel(u_Atlas, v_TexCoord)));
+}
+#endif
@@ -1,8 +1,17 @@
+#ifdef GRADIENT
+varying vec4 v_Gradient;
+varying vec4 v_Offsets;
+varying vec4 v_Color0;
+varying vec4 v_Color1;
+varying vec4 v_Color2;
+varying vec4 v_Color3;
+#else
 varying vec4 v_Color;
+#endif
 varying vec4 v_BorderColor;
 varying vec2 v_Pos;
 varying vec2 v_Scale;
-varying float v_BorderRadius;
+varying vec4 v_BorderRadius;
 varying float v_BorderWidth;
 
 #ifdef VERTEX
@@ -12,9 +21,23 @@ uniform float u_Scale;
 attribute vec2 position;
 attribute vec2 i_Pos;
 attribute vec2 i_Scale;
+#ifdef GRADIENT
+attribute vec4 i_Gradient;
+attribute vec4 i_Offsets;
+attribute vec4 i_Color0;
+attribute vec4 i_Color1;
+attribute vec4 i_Color2;
+attribute vec4 i_Color3;
+#else
 attribute vec4 i_Color;
+#endif
 attribute vec4 i_BorderColor;
+#ifdef GRADIENT
+// The radii of the top left, top right, bottom right and bottom left corners
+attribute vec4 i_BorderRadius;
+#else
 attribute float i_BorderRadius;
+#endif
 attribute float i_BorderWidth;
 
 void main() {
@@ -22,9 +45,9 @@ void main() {
     vec2 p_Pos = i_Pos * u_Scale;
     vec2 p_Scale = i_Scale  * u_Scale;
 
-    float i_BorderRadius = min(
-        i_BorderRadius,
-        min(i_Scale.x, i_Scale.y) / 2.0
+    vec4 border_radius = min(
+        vec4(i_BorderRadius),
+        vec4(min(i_Scale.x, i_Scale.y) / 2.0)
     );
 
     mat4 i_Transform = mat4(
@@ -34,11 +57,21 @@ void main() {
         vec4(p_Pos - vec2(0.5, 0.5), 0.0, 1.0)
     );
 
+#ifdef GRADIENT
+    // Only the radius of radial gradients is in logical pixels
+    v_Gradient = vec4(i_Gradient.xy, i_Gradient.z * u_Scale, i_Gradient.w);
+    v_Offsets = i_Offsets;
+    v_Color0 = i_Color0;
+    v_Color1 = i_Color1;
+    v_Color2 = i_Color2;
+    v_Color3 = i_Color3;
+#else
     v_Color = i_Color;
+#endif
     v_BorderColor = i_BorderColor;
     v_Pos = p_Pos;
     v_Scale = p_Scale;
-    v_BorderRadius = i_BorderRadius * u_Scale;
+    v_BorderRadius = border_radius * u_Scale;
     v_BorderWidth = i_BorderWidth * u_Scale;
 
     gl_Position = u_Transform * i_Transform * vec4(q_Pos, 0.0, 1.0);
@@ -48,61 +81,75 @@ void main() {
 #ifdef FRAGMENT
 uniform float u_ScreenHeight;
 
-float distance(in vec2 frag_coord, in vec2 position, in vec2 size, float radius)
+// The signed distance from a point to a box with rounded corners, negative
+// inside of it: https://iquilezles.org/articles/distfunctions2d/
+float rounded_box_sdf(in vec2 frag_coord, in vec2 position, in vec2 size, vec4 radii)
 {
-    // TODO: Try SDF approach: https://www.shadertoy.com/view/wd3XRN
-    vec2 inner_size = size - vec2(radius, radius) * 2.0;
-    vec2 top_left = position + vec2(radius, radius);
-    vec2 bottom_right = top_left + inner_size;
+    vec2 half_size = size * 0.5;
+    vec2 p = frag_coord - (position + half_size);
 
-    vec2 top_left_distance = top_left - frag_coord;
-    vec2 bottom_right_distance = frag_coord - bottom_right;
+    // Pick the radius of the corner of the quadrant the point is in
+    vec2 side_radii = p.x > 0.0 ? radii.yz : radii.xw;
+    float radius = p.y > 0.0 ? side_radii.y : side_radii.x;
 
-    vec2 distance = vec2(
-    max(max(top_left_distance.x, bottom_right_distance.x), 0.0),
-    max(max(top_left_distance.y, bottom_right_distance.y), 0.0)
-    );
+    vec2 q = abs(p) - half_size + radius;
 
-    return sqrt(distance.x * distance.x + distance.y * distance.y);
+    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - radius;
 }
 
+#ifdef GRADIENT
+float stop_mix(float t, float from, float to) {
+    return to > from ? clamp((t - from) / (to - from), 0.0, 1.0) : step(to, t);
+}
+
+vec4 gradient_color(in vec2 frag_coord) {
+    vec2 local = frag_coord - v_Pos;
+    float t;
+
+    if (v_Gradient.w > 0.5) {
+        t = length(local - v_Gradient.xy * v_Scale) / max(v_Gradient.z, 0.0001);
+    } else {
+        // The ends of the line touch the corners of the quad
+        vec2 direction = v_Gradient.xy;
+        float extent = abs(direction.x) * v_Scale.x + abs(direction.y) * v_Scale.y;
+
+        t = dot(local - v_Scale * 0.5, direction) / max(extent, 0.0001) + 0.5;
+    }
+
+    vec4 color = v_Color0;
+    color = mix(color, v_Color1, stop_mix(t, v_Offsets.x, v_Offsets.y));
+    color = mix(color, v_Color2, stop_mix(t, v_Offsets.y, v_Offsets.z));
+    color = mix(color, v_Color3, stop_mix(t, v_Offsets.z, v_Offsets.w));
+
+    return color;
+}
+#endif
+
 void main() {
     vec4 mixed_color;
 
     vec2 fragCoord = vec2(gl_FragCoord.x, u_ScreenHeight - gl_FragCoord.y);
 
-    // TODO: Remove branching (?)
-    if(v_BorderWidth > 0.0) {
-        float internal_border = max(v_BorderRadius - v_BorderWidth, 0.0);
+#ifdef GRADIENT
+    vec4 fill_color = gradient_color(fragCoord);
+#else
+    vec4 fill_color = v_Color;
+#endif
 
-        float internal_distance = distance(
-            fragCoord,
-            v_Pos + vec2(v_BorderWidth),
-            v_Scale - vec2(v_BorderWidth * 2.0),
-            internal_border
-        );
+    float d = rounded_box_sdf(fragCoord, v_Pos, v_Scale, v_BorderRadius);
 
-        float border_mix = smoothstep(
-            max(internal_border - 0.5, 0.0),
-            internal_border + 0.5,
-            internal_distance
-        );
+    if (v_BorderWidth > 0.0) {
+        // Offsetting the distance insets the box and shrinks its corners
+        float border_mix = clamp(0.5 + d + v_BorderWidth, 0.0, 1.0);
 
-        mixed_color = mix(v_Color, v_BorderColor, border_mix);
+        mixed_color = mix(fill_color, v_BorderColor, border_mix);
     } else {
-        mixed_color = v_Color;
+        mixed_color = fill_color;
     }
 
-    float d = distance(
-        fragCoord,
-        v_Pos,
-        v_Scale,
-        v_BorderRadius
-    );
-
-    float radius_alpha = 1.0 - smoothstep(max(v_BorderRadius - 0.5, 0.0), v_BorderRadius + 0.5, d);
+    float radius_alpha = clamp(0.5 - d, 0.0, 1.0);
 
-    fragColor = vec4(mixed_color.xyz, mixed_color.w * radius_alpha);
+    fragColor = to_framebuffer(vec4(mixed_color.xyz, mixed_color.w * radius_alpha));
 }
 
 #endif
\ No newline at end of file
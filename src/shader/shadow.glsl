@@ -0,0 +1,119 @@
+varying vec4 v_Color;
+varying vec2 v_Lower;
+varying vec2 v_Upper;
+varying vec4 v_Radius;
+varying float v_Sigma;
+
+#ifdef VERTEX
+uniform mat4 u_Transform;
+uniform float u_Scale;
+
+attribute vec2 position;
+attribute vec2 i_Pos;
+attribute vec2 i_Scale;
+attribute vec4 i_Color;
+attribute vec2 i_Offset;
+// The radii of the top left, top right, bottom right and bottom left corners
+attribute vec4 i_BorderRadius;
+attribute float i_BlurRadius;
+attribute float i_Spread;
+
+void main() {
+    // The blur radius covers two standard deviations, like in CSS
+    float sigma = max(i_BlurRadius * u_Scale * 0.5, 0.5);
+
+    vec2 lower = (i_Pos + i_Offset - vec2(i_Spread)) * u_Scale;
+    vec2 upper = (i_Pos + i_Scale + i_Offset + vec2(i_Spread)) * u_Scale;
+    vec2 size = max(upper - lower, vec2(0.0));
+
+    vec4 radius = min(
+        max(i_BorderRadius + vec4(i_Spread), vec4(0.0)) * u_Scale,
+        vec4(min(size.x, size.y) / 2.0)
+    );
+
+    // The gaussian fades out completely after three standard deviations
+    vec2 margin = vec2(sigma * 3.0 + 1.0);
+    vec2 p_Pos = lower - margin;
+    vec2 p_Scale = size + margin * 2.0;
+
+    mat4 i_Transform = mat4(
+        vec4(p_Scale.x, 0.0, 0.0, 0.0),
+        vec4(0.0, p_Scale.y, 0.0, 0.0),
+        vec4(0.0, 0.0, 1.0, 0.0),
+        vec4(p_Pos, 0.0, 1.0)
+    );
+
+    v_Color = i_Color;
+    v_Lower = lower;
+    v_Upper = lower + size;
+    v_Radius = radius;
+    v_Sigma = sigma;
+
+    gl_Position = u_Transform * i_Transform * vec4(position, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+uniform float u_ScreenHeight;
+
+// Analytical rounded box shadow, as described in
+// https://madebyevan.com/shaders/fast-rounded-rectangle-shadows/
+float gaussian(float x, float sigma) {
+    const float pi = 3.141592653589793;
+
+    return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(2.0 * pi) * sigma);
+}
+
+vec2 erf(vec2 x) {
+    vec2 s = sign(x);
+    vec2 a = abs(x);
+
+    x = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    x *= x;
+
+    return s - s / (x * x);
+}
+
+float shadow_x(float x, float y, float sigma, vec4 radii, vec2 half_size) {
+    // The radii of the left and right corners of the row
+    vec2 radius = y < 0.0 ? radii.xy : radii.wz;
+
+    vec2 delta = min(half_size.y - radius - abs(y), vec2(0.0));
+    vec2 curved = half_size.x - radius + sqrt(max(vec2(0.0), radius * radius - delta * delta));
+    vec2 integral = 0.5 + 0.5 * erf((x + vec2(-curved.x, curved.y)) * (sqrt(0.5) / sigma));
+
+    return integral.y - integral.x;
+}
+
+float shadow(vec2 lower, vec2 upper, vec2 point, float sigma, vec4 radii) {
+    vec2 center = (lower + upper) * 0.5;
+    vec2 half_size = (upper - lower) * 0.5;
+    point -= center;
+
+    // The shadow along the y axis is integrated with a few samples
+    float low = point.y - half_size.y;
+    float high = point.y + half_size.y;
+    float start = clamp(-3.0 * sigma, low, high);
+    float end = clamp(3.0 * sigma, low, high);
+
+    float step = (end - start) / 4.0;
+    float y = start + step * 0.5;
+    float value = 0.0;
+
+    for (int i = 0; i < 4; i++) {
+        value += shadow_x(point.x, point.y - y, sigma, radii, half_size)
+            * gaussian(y, sigma) * step;
+        y += step;
+    }
+
+    return value;
+}
+
+void main() {
+    vec2 fragCoord = vec2(gl_FragCoord.x, u_ScreenHeight - gl_FragCoord.y);
+
+    float alpha = shadow(v_Lower, v_Upper, fragCoord, v_Sigma, v_Radius);
+
+    fragColor = to_framebuffer(vec4(v_Color.rgb, v_Color.a * alpha));
+}
+#endif
@@ -14,6 +14,10 @@ void main() {
 
 #ifdef FRAGMENT
 void main() {
-    fragColor = v_Color;
+    vec4 color = to_framebuffer(v_Color);
+
+    // Meshes are blended premultiplied, so they compose correctly into the
+    // transparent multisampled target
+    fragColor = vec4(color.rgb * color.a, color.a);
 }
 #endif
\ No newline at end of file
@@ -1,19 +1,55 @@
+#[cfg(feature = "color_emoji")]
+mod color;
+
+use crate::settings::{self, ColorSpace, FontFallback, GlyphCache};
 use crate::Transformation;
+use glyph_brush::GlyphPositioner;
 use iced_graphics::font;
+use iced_graphics::layer;
+use iced_native::alignment;
 use iced_native::text::Hit;
 use solstice_glyph::ab_glyph;
+#[cfg(feature = "color_emoji")]
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::{cell::RefCell, collections::HashMap};
 
+/// Measures, hit tests and draws text.
+///
+/// Text is laid out once in logical coordinates and only scaled when queued
+/// for drawing, so it wraps the same way at every scale factor. Alignment
+/// only moves the lines of a layout around, so measuring and drawing some
+/// text share the same layout too.
 #[derive(Debug)]
 pub struct Pipeline {
     draw_brush: RefCell<solstice_glyph::GlyphBrush>,
     draw_font_map: RefCell<HashMap<String, solstice_glyph::FontId>>,
-    measure_brush: RefCell<glyph_brush::GlyphBrush<()>>,
+    fonts: RefCell<Vec<ab_glyph::FontArc>>,
+    layouts: RefCell<HashMap<u64, Vec<Paragraph>>>,
+    generation: u64,
+    glyph_cache: GlyphCache,
+    fallbacks: Vec<solstice_glyph::FontId>,
+    font_fallbacks: HashMap<&'static str, Vec<solstice_glyph::FontId>>,
+    /// The fallbacks of every external font loaded so far, before the
+    /// global ones.
+    chains: RefCell<HashMap<solstice_glyph::FontId, Vec<solstice_glyph::FontId>>>,
+    #[cfg(feature = "color_emoji")]
+    color: RefCell<color::Pipeline>,
 }
 
 impl Pipeline {
-    pub fn new(gl: &mut solstice::Context, default_font: Option<&[u8]>) -> Self {
-        let default_font = default_font.map(|slice| slice.to_vec());
+    pub fn new(
+        gl: &mut solstice::Context,
+        default_font: Option<(&[u8], u32)>,
+        glyph_cache: GlyphCache,
+        font_fallback: FontFallback,
+        color_space: ColorSpace,
+    ) -> Self {
+        let (default_font, default_index) = match default_font {
+            Some((bytes, index)) => (Some(bytes.to_vec()), index),
+            None => (None, 0),
+        };
 
         // TODO: Font customization
         #[cfg(feature = "default_system_font")]
@@ -27,43 +63,234 @@ impl Pipeline {
 
         let default_font = default_font.unwrap_or_else(|| font::FALLBACK.to_vec());
 
-        let font = ab_glyph::FontArc::try_from_vec(default_font).unwrap_or_else(|_| {
-            log::warn!(
-                "System font failed to load. Falling back to \
-                    embedded font..."
-            );
+        // Color glyphs are read from the tables of the font files
+        #[cfg(feature = "color_emoji")]
+        let mut color = color::Pipeline::new(gl, color_space);
+        #[cfg(feature = "color_emoji")]
+        let mut default_source = (Cow::Owned(default_font.clone()), default_index);
+        #[cfg(not(feature = "color_emoji"))]
+        let _ = color_space;
+
+        let font = ab_glyph::FontVec::try_from_vec_and_index(default_font, default_index)
+            .map(ab_glyph::FontArc::new)
+            .unwrap_or_else(|_| {
+                log::warn!(
+                    "System font failed to load. Falling back to \
+                        embedded font..."
+                );
+
+                #[cfg(feature = "color_emoji")]
+                {
+                    default_source = (Cow::Borrowed(font::FALLBACK), 0);
+                }
 
-            ab_glyph::FontArc::try_from_slice(font::FALLBACK).expect("Load fallback font")
-        });
+                ab_glyph::FontArc::try_from_slice(font::FALLBACK).expect("Load fallback font")
+            });
+
+        #[cfg(feature = "color_emoji")]
+        color.add_font(default_source.0, default_source.1);
+
+        let mut fonts = vec![font];
+        let mut loaded = HashMap::new();
 
-        let draw_brush = solstice_glyph::GlyphBrushBuilder::using_font(font.clone())
-            .initial_cache_size((2048, 2048))
-            .draw_cache_multithread(false) // TODO: Expose as a configuration flag
-            .build(gl);
+        // Fallback fonts may appear in many chains, but are only loaded once
+        let mut load_chain = |chain: &'static [&'static [u8]]| {
+            chain
+                .iter()
+                .filter_map(|bytes| {
+                    let key = (bytes.as_ptr(), bytes.len());
 
-        let measure_brush = glyph_brush::GlyphBrushBuilder::using_font(font).build();
+                    if let Some(font_id) = loaded.get(&key) {
+                        return Some(*font_id);
+                    }
+
+                    match load_font(bytes, 0) {
+                        Ok(font) => {
+                            fonts.push(font);
+
+                            #[cfg(feature = "color_emoji")]
+                            color.add_font(Cow::Borrowed(bytes), 0);
+
+                            let font_id = solstice_glyph::FontId(fonts.len() - 1);
+                            let _ = loaded.insert(key, font_id);
+
+                            Some(font_id)
+                        }
+                        Err(error) => {
+                            log::warn!("Fallback font failed to load: {}", error);
+
+                            None
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let fallbacks = load_chain(font_fallback.fonts);
+        let font_fallbacks = font_fallback
+            .per_font
+            .iter()
+            .map(|(name, chain)| (*name, load_chain(chain)))
+            .collect();
+
+        let draw_brush = build_brush(gl, fonts.clone(), &glyph_cache);
 
         Pipeline {
             draw_brush: RefCell::new(draw_brush),
             draw_font_map: RefCell::new(HashMap::new()),
-            measure_brush: RefCell::new(measure_brush),
+            fonts: RefCell::new(fonts),
+            layouts: RefCell::new(HashMap::new()),
+            generation: 0,
+            glyph_cache,
+            fallbacks,
+            font_fallbacks,
+            chains: RefCell::new(HashMap::new()),
+            #[cfg(feature = "color_emoji")]
+            color: RefCell::new(color),
         }
     }
 
-    pub fn queue(&mut self, section: solstice_glyph::Section<'_>) {
-        self.draw_brush.borrow_mut().queue(section);
+    /// Queues the given text to be drawn at the given scale factor.
+    ///
+    /// Color glyphs are queued apart, as the glyph brush only draws outlines.
+    pub fn queue(&mut self, text: &layer::Text<'_>, scale_factor: f32, color: [f32; 4]) {
+        let key = Key::new(text.content, text.size, self.find_font(text.font), text.bounds.width);
+
+        // Glyphs keep their subpixel positions. The draw cache reuses the
+        // rasterization of a glyph within the position tolerance of its
+        // offset, so moving text does not rerasterize it every frame
+        let origin = ab_glyph::point(text.bounds.x * scale_factor, text.bounds.y * scale_factor);
+
+        let glyphs: Vec<_> = self.with_layout(&key, |paragraph| {
+            let height = paragraph.bounds.map_or(0.0, |bounds| bounds.max.y);
+            let top = match text.vertical_alignment {
+                alignment::Vertical::Top => 0.0,
+                alignment::Vertical::Center => -height / 2.0,
+                alignment::Vertical::Bottom => -height,
+            };
+
+            paragraph
+                .lines
+                .iter()
+                .flat_map(|line| {
+                    let left = match text.horizontal_alignment {
+                        alignment::Horizontal::Left => 0.0,
+                        alignment::Horizontal::Center => -line.width / 2.0,
+                        alignment::Horizontal::Right => -line.width,
+                    };
+
+                    paragraph.glyphs[line.glyphs.clone()]
+                        .iter()
+                        .map(move |glyph| (glyph, ab_glyph::point(left, top)))
+                })
+                .map(|(section_glyph, offset)| {
+                    let mut section_glyph = section_glyph.clone();
+                    let glyph = &mut section_glyph.glyph;
+
+                    glyph.position = origin + (glyph.position + offset) * scale_factor;
+                    glyph.scale = ab_glyph::PxScale {
+                        x: glyph.scale.x * scale_factor,
+                        y: glyph.scale.y * scale_factor,
+                    };
+
+                    section_glyph
+                })
+                .collect()
+        });
+
+        #[cfg(feature = "color_emoji")]
+        let glyphs = {
+            let color_pipeline = self.color.get_mut();
+            let fonts = self.fonts.borrow();
+
+            // The color of the text is linear, but color fonts paint their
+            // layers with sRGB colors
+            let foreground = ColorSpace::LegacyGamma
+                .encode(text.color)
+                .map(|component| (component.max(0.0).min(1.0) * 255.0).round() as u8);
+
+            glyphs
+                .into_iter()
+                .filter(|glyph| !color_pipeline.queue(glyph, &fonts, foreground, text.color[3]))
+                .collect()
+        };
+
+        // Like the glyphs, the bounds are anchored by their alignment
+        let (width, height) = (
+            text.bounds.width * scale_factor,
+            text.bounds.height * scale_factor,
+        );
+        let min = ab_glyph::point(
+            match text.horizontal_alignment {
+                alignment::Horizontal::Left => origin.x,
+                alignment::Horizontal::Center => origin.x - width / 2.0,
+                alignment::Horizontal::Right => origin.x - width,
+            },
+            match text.vertical_alignment {
+                alignment::Vertical::Top => origin.y,
+                alignment::Vertical::Center => origin.y - height / 2.0,
+                alignment::Vertical::Bottom => origin.y - height,
+            },
+        );
+        let bounds = ab_glyph::Rect {
+            min,
+            max: ab_glyph::point(min.x + width, min.y + height),
+        };
+
+        self.draw_brush.borrow_mut().queue_pre_positioned(
+            glyphs,
+            vec![solstice_glyph::Extra { color, z: 0.0 }],
+            bounds,
+        );
     }
 
     pub fn draw_queued(
         &mut self,
         gl: &mut solstice::Context,
+        target: Option<&solstice::canvas::Canvas>,
         transformation: Transformation,
         region: solstice_glyph::Region,
     ) {
+        #[cfg(feature = "color_emoji")]
+        let scissor = solstice::viewport::Viewport::new(
+            region.x as i32,
+            region.y as i32,
+            region.width as i32,
+            region.height as i32,
+        );
+
         self.draw_brush
             .borrow_mut()
-            .draw_queued_with_transform_and_scissoring(gl, transformation.into(), region)
+            .draw_queued_with_transform_and_scissoring(gl, target, transformation.into(), region)
             .expect("Draw text");
+
+        #[cfg(feature = "color_emoji")]
+        self.color.get_mut().draw(
+            gl,
+            target,
+            &self.fonts.borrow(),
+            transformation,
+            scissor,
+        );
+
+        let (width, height) = self.draw_brush.borrow().texture_dimensions();
+        let (max_width, max_height) = self.glyph_cache.max_size;
+
+        if width > max_width || height > max_height {
+            // Text needing this much every frame rasterizes its glyphs again
+            // every frame, so this should stay rare
+            log::warn!(
+                "Glyph atlas grew to {}x{} past its maximum size of {}x{}, rebuilding it \
+                 with its initial size. Raise `GlyphCache::max_size` if this keeps happening",
+                width,
+                height,
+                max_width,
+                max_height
+            );
+
+            let fonts = self.fonts.borrow().clone();
+            *self.draw_brush.get_mut() = build_brush(gl, fonts, &self.glyph_cache);
+        }
     }
 
     pub fn measure(
@@ -73,27 +300,12 @@ impl Pipeline {
         font: iced_native::Font,
         bounds: iced_native::Size,
     ) -> (f32, f32) {
-        let solstice_glyph::FontId(font_id) = self.find_font(font);
-
-        let section = solstice_glyph::Section {
-            bounds: (bounds.width, bounds.height),
-            text: vec![solstice_glyph::Text {
-                text: content,
-                scale: size.into(),
-                font_id: solstice_glyph::FontId(font_id),
-                extra: solstice_glyph::Extra::default(),
-            }],
-            ..Default::default()
-        };
+        let key = Key::new(content, size, self.find_font(font), bounds.width);
 
-        if let Some(bounds) = solstice_glyph::GlyphCruncher::glyph_bounds(
-            &mut *self.measure_brush.borrow_mut(),
-            section,
-        ) {
-            (bounds.width().ceil(), bounds.height().ceil())
-        } else {
-            (0.0, 0.0)
-        }
+        self.with_layout(&key, |paragraph| match paragraph.bounds {
+            Some(bounds) => (bounds.width().ceil(), bounds.height().ceil()),
+            None => (0.0, 0.0),
+        })
     }
 
     pub fn hit_test(
@@ -105,103 +317,168 @@ impl Pipeline {
         point: iced_native::Point,
         nearest_only: bool,
     ) -> Option<Hit> {
-        use solstice_glyph::GlyphCruncher;
-
-        let solstice_glyph::FontId(font_id) = self.find_font(font);
-
-        let section = solstice_glyph::Section {
-            bounds: (bounds.width, bounds.height),
-            text: vec![solstice_glyph::Text {
-                text: content,
-                scale: size.into(),
-                font_id: solstice_glyph::FontId(font_id),
-                extra: solstice_glyph::Extra::default(),
-            }],
-            ..Default::default()
-        };
-
-        let mut mb = self.measure_brush.borrow_mut();
+        let key = Key::new(content, size, self.find_font(font), bounds.width);
 
         // The underlying type is FontArc, so clones are cheap.
         use ab_glyph::{Font, ScaleFont};
-        let font = mb.fonts()[font_id].clone().into_scaled(size);
-
-        // Implements an iterator over the glyph bounding boxes.
-        let bounds = mb.glyphs(section).map(
-            |solstice_glyph::SectionGlyph {
-                 byte_index, glyph, ..
-             }| {
-                (
-                    *byte_index,
-                    iced_native::Rectangle::new(
-                        iced_native::Point::new(
-                            glyph.position.x - font.h_side_bearing(glyph.id),
-                            glyph.position.y - font.ascent(),
-                        ),
-                        iced_native::Size::new(
-                            font.h_advance(glyph.id),
-                            font.ascent() - font.descent(),
+        let fonts = self.fonts.borrow().clone();
+
+        self.with_layout(&key, |paragraph| {
+            // Implements an iterator over the glyph bounding boxes.
+            let bounds = paragraph.glyphs.iter().map(
+                |solstice_glyph::SectionGlyph {
+                     byte_index,
+                     glyph,
+                     font_id,
+                     ..
+                 }| {
+                    // Fallback glyphs are measured with their own font
+                    let font = fonts[font_id.0].as_scaled(size);
+
+                    (
+                        *byte_index,
+                        iced_native::Rectangle::new(
+                            iced_native::Point::new(
+                                glyph.position.x - font.h_side_bearing(glyph.id),
+                                glyph.position.y - font.ascent(),
+                            ),
+                            iced_native::Size::new(
+                                font.h_advance(glyph.id),
+                                font.ascent() - font.descent(),
+                            ),
                         ),
-                    ),
-                )
-            },
-        );
+                    )
+                },
+            );
 
-        // Implements computation of the character index based on the byte index
-        // within the input string.
-        let char_index = |byte_index| {
-            let mut b_count = 0;
-            for (i, utf8_len) in content.chars().map(|c| c.len_utf8()).enumerate() {
-                if byte_index < (b_count + utf8_len) {
-                    return i;
+            // Implements computation of the character index based on the byte index
+            // within the input string.
+            let char_index = |byte_index| {
+                let mut b_count = 0;
+                for (i, utf8_len) in content.chars().map(|c| c.len_utf8()).enumerate() {
+                    if byte_index < (b_count + utf8_len) {
+                        return i;
+                    }
+                    b_count += utf8_len;
                 }
-                b_count += utf8_len;
-            }
-            return byte_index;
-        };
-
-        if !nearest_only {
-            for (idx, bounds) in bounds.clone() {
-                if bounds.contains(point) {
-                    return Some(Hit::CharOffset(char_index(idx)));
+                return byte_index;
+            };
+
+            if !nearest_only {
+                for (idx, bounds) in bounds.clone() {
+                    if bounds.contains(point) {
+                        return Some(Hit::CharOffset(char_index(idx)));
+                    }
                 }
             }
-        }
 
-        let nearest = bounds
-            .map(|(index, bounds)| (index, bounds.center()))
-            .min_by(|(_, center_a), (_, center_b)| {
-                center_a
-                    .distance(point)
-                    .partial_cmp(&center_b.distance(point))
-                    .unwrap_or(std::cmp::Ordering::Greater)
+            let nearest = bounds
+                .map(|(index, bounds)| (index, bounds.center()))
+                .min_by(|(_, center_a), (_, center_b)| {
+                    center_a
+                        .distance(point)
+                        .partial_cmp(&center_b.distance(point))
+                        .unwrap_or(std::cmp::Ordering::Greater)
+                });
+
+            nearest.map(|(idx, center)| Hit::NearestCharOffset(char_index(idx), point - center))
+        })
+    }
+
+    /// Drops the layouts that have been idle for longer than the
+    /// [`settings::Trim`] policy allows.
+    ///
+    /// It is called after every user interface layout.
+    pub fn trim_measurement_cache(&mut self) {
+        if let settings::Trim::Idle(layouts) = self.glyph_cache.trim {
+            let generation = self.generation;
+
+            self.layouts.get_mut().retain(|_, paragraphs| {
+                paragraphs.retain(|paragraph| {
+                    generation - paragraph.last_used < u64::from(layouts.max(1))
+                });
+
+                !paragraphs.is_empty()
             });
+        }
 
-        nearest.map(|(idx, center)| Hit::NearestCharOffset(char_index(idx), point - center))
+        self.generation += 1;
     }
 
-    pub fn trim_measurement_cache(&mut self) {
-        // TODO: We should probably use a `GlyphCalculator` for this. However,
-        // it uses a lifetimed `GlyphCalculatorGuard` with side-effects on drop.
-        // This makes stuff quite inconvenient. A manual method for trimming the
-        // cache would make our lives easier.
-        loop {
-            let action = self
-                .measure_brush
-                .borrow_mut()
-                .process_queued(|_, _| {}, |_| {});
-
-            match action {
-                Ok(_) => break,
-                Err(glyph_brush::BrushError::TextureTooSmall { suggested }) => {
-                    let (width, height) = suggested;
-
-                    self.measure_brush
-                        .borrow_mut()
-                        .resize_texture(width, height);
-                }
+    /// Calls `f` with the [`Paragraph`] of the given [`Key`], laying it out
+    /// first if it is not cached.
+    fn with_layout<T>(&self, key: &Key<'_>, f: impl FnOnce(&Paragraph) -> T) -> T {
+        let mut layouts = self.layouts.borrow_mut();
+        let paragraphs = layouts.entry(key.hash()).or_default();
+
+        // Keys are compared in full, as different texts may share a hash
+        let index = match paragraphs.iter().position(|paragraph| key.matches(paragraph)) {
+            Some(index) => index,
+            None => {
+                paragraphs.push(self.lay_out(key));
+                paragraphs.len() - 1
             }
+        };
+
+        let paragraph = &mut paragraphs[index];
+        paragraph.last_used = self.generation;
+
+        f(paragraph)
+    }
+
+    /// Lays out the text of the given [`Key`] from the top left corner.
+    fn lay_out(&self, key: &Key<'_>) -> Paragraph {
+        let fonts = self.fonts.borrow();
+        let runs = runs(&fonts, &self.fallback_chain(key.font_id), key.content);
+
+        let sections: Vec<_> = runs
+            .iter()
+            .map(|run| glyph_brush::SectionText {
+                text: &key.content[run.start..run.end],
+                scale: key.size.into(),
+                font_id: run.font_id,
+            })
+            .collect();
+
+        let mut glyphs = glyph_brush::Layout::default().calculate_glyphs(
+            &fonts,
+            &glyph_brush::SectionGeometry {
+                screen_position: (0.0, 0.0),
+                bounds: (key.width, f32::INFINITY),
+            },
+            &sections,
+        );
+
+        // Byte indices are relative to the run of each glyph, but we want
+        // them relative to the whole content
+        for glyph in &mut glyphs {
+            glyph.byte_index += runs[glyph.section_index].start;
+            glyph.section_index = 0;
         }
+
+        let bounds = glyph_bounds(&fonts, &glyphs);
+        let lines = lines(&fonts, &glyphs);
+
+        Paragraph {
+            content: key.content.to_owned(),
+            size: key.size,
+            font_id: key.font_id,
+            width: key.width,
+            glyphs,
+            lines,
+            bounds,
+            last_used: self.generation,
+        }
+    }
+
+    /// Returns the fonts glyphs of the given font are looked up in, in order.
+    fn fallback_chain(&self, font_id: solstice_glyph::FontId) -> Vec<solstice_glyph::FontId> {
+        let chains = self.chains.borrow();
+
+        std::iter::once(font_id)
+            .chain(chains.get(&font_id).into_iter().flatten().copied())
+            .chain(self.fallbacks.iter().copied())
+            .collect()
     }
 
     pub fn find_font(&self, font: iced_native::Font) -> solstice_glyph::FontId {
@@ -212,9 +489,13 @@ impl Pipeline {
                     return *font_id;
                 }
 
-                let font = ab_glyph::FontArc::try_from_slice(bytes).expect("Load font");
+                let index = face_index(name, bytes);
+                let font = load_font(bytes, index).expect("Load font");
 
-                let _ = self.measure_brush.borrow_mut().add_font(font.clone());
+                self.fonts.borrow_mut().push(font.clone());
+
+                #[cfg(feature = "color_emoji")]
+                self.color.borrow_mut().add_font(Cow::Borrowed(bytes), index);
 
                 let font_id = self.draw_brush.borrow_mut().add_font(font);
 
@@ -223,8 +504,289 @@ impl Pipeline {
                     .borrow_mut()
                     .insert(String::from(name), font_id);
 
+                if let Some(chain) = self.font_fallbacks.get(name) {
+                    let _ = self.chains.borrow_mut().insert(font_id, chain.clone());
+                }
+
                 font_id
             }
         }
     }
 }
+
+/// A range of text drawn with a single font.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: usize,
+    end: usize,
+    font_id: solstice_glyph::FontId,
+}
+
+/// Splits the content into runs of characters sharing the first font of the
+/// chain that covers them.
+///
+/// Characters no font covers use the first font of the chain.
+fn runs(fonts: &[ab_glyph::FontArc], chain: &[solstice_glyph::FontId], content: &str) -> Vec<Run> {
+    use ab_glyph::Font;
+
+    let mut runs: Vec<Run> = Vec::new();
+
+    for (index, character) in content.char_indices() {
+        let end = index + character.len_utf8();
+
+        let font_id = match runs.last() {
+            // Whitespace and control characters never start a new run
+            Some(run) if character.is_whitespace() || character.is_control() => run.font_id,
+            _ => chain
+                .iter()
+                .copied()
+                .find(|font_id| fonts[font_id.0].glyph_id(character).0 != 0)
+                .unwrap_or(chain[0]),
+        };
+
+        match runs.last_mut() {
+            Some(run) if run.font_id == font_id => run.end = end,
+            _ => runs.push(Run {
+                start: index,
+                end,
+                font_id,
+            }),
+        }
+    }
+
+    runs
+}
+
+/// Loads the face of the given index from the font of the given bytes.
+fn load_font(
+    bytes: &'static [u8],
+    index: u32,
+) -> Result<ab_glyph::FontArc, ab_glyph::InvalidFont> {
+    ab_glyph::FontRef::try_from_slice_and_index(bytes, index).map(ab_glyph::FontArc::new)
+}
+
+/// Returns the index of the face of the given PostScript name in the font of
+/// the given bytes.
+///
+/// Only collections, like the ones system fonts are loaded from, hold more
+/// than one face. Any other font is read from its first face.
+fn face_index(name: &str, bytes: &[u8]) -> u32 {
+    #[cfg(feature = "ttf-parser")]
+    {
+        let count = ttf_parser::fonts_in_collection(bytes).unwrap_or(1);
+
+        (0..count)
+            .find(|index| {
+                ttf_parser::Face::parse(bytes, *index).map_or(false, |face| {
+                    face.names().into_iter().any(|entry| {
+                        entry.name_id == ttf_parser::name_id::POST_SCRIPT_NAME
+                            && entry.to_string().as_deref() == Some(name)
+                    })
+                })
+            })
+            .unwrap_or(0)
+    }
+
+    #[cfg(not(feature = "ttf-parser"))]
+    {
+        let _ = (name, bytes);
+
+        0
+    }
+}
+
+fn build_brush(
+    gl: &mut solstice::Context,
+    fonts: Vec<ab_glyph::FontArc>,
+    glyph_cache: &GlyphCache,
+) -> solstice_glyph::GlyphBrush {
+    solstice_glyph::GlyphBrushBuilder::using_fonts(fonts)
+        .initial_cache_size(glyph_cache.initial_size)
+        .draw_cache_position_tolerance(1.0 / f32::from(glyph_cache.subpixel_positions.max(1)))
+        .draw_cache_scale_tolerance(1.0 / f32::from(glyph_cache.scale_steps.max(1)))
+        .draw_cache_multithread(glyph_cache.multithread)
+        .build(gl)
+}
+
+/// The parameters a [`Paragraph`] is laid out with.
+#[derive(Debug, Clone, Copy)]
+struct Key<'a> {
+    content: &'a str,
+    size: f32,
+    font_id: solstice_glyph::FontId,
+    /// The width the text wraps at.
+    width: f32,
+}
+
+impl<'a> Key<'a> {
+    fn new(content: &'a str, size: f32, font_id: solstice_glyph::FontId, width: f32) -> Self {
+        Key {
+            content,
+            size,
+            font_id,
+            width,
+        }
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        self.content.hash(&mut hasher);
+        self.size.to_bits().hash(&mut hasher);
+        self.font_id.0.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Returns whether the given [`Paragraph`] is laid out like this [`Key`]
+    /// would be.
+    ///
+    /// Text is drawn within the width it was measured at, rather than the
+    /// width it was measured with. Wrapping at any width between the two
+    /// breaks the same lines, so both share a paragraph.
+    fn matches(&self, paragraph: &Paragraph) -> bool {
+        let wraps_alike = paragraph.width.to_bits() == self.width.to_bits()
+            || (paragraph.measured_width() <= self.width && self.width <= paragraph.width);
+
+        paragraph.content == self.content
+            && paragraph.size.to_bits() == self.size.to_bits()
+            && paragraph.font_id == self.font_id
+            && wraps_alike
+    }
+}
+
+/// Text laid out in logical coordinates from the top left corner.
+#[derive(Debug)]
+struct Paragraph {
+    content: String,
+    size: f32,
+    font_id: solstice_glyph::FontId,
+    width: f32,
+    glyphs: Vec<solstice_glyph::SectionGlyph>,
+    lines: Vec<Line>,
+    bounds: Option<ab_glyph::Rect>,
+    /// The generation of the cache the paragraph was last used in.
+    last_used: u64,
+}
+
+impl Paragraph {
+    /// The width of the widest line, as reported by a measurement.
+    fn measured_width(&self) -> f32 {
+        self.bounds.map_or(0.0, |bounds| bounds.width().ceil())
+    }
+}
+
+/// A line of a [`Paragraph`].
+#[derive(Debug, Clone)]
+struct Line {
+    /// The range of the glyphs of the line.
+    glyphs: std::ops::Range<usize>,
+    /// The distance from the start of the line to the end of its last glyph.
+    width: f32,
+}
+
+/// Splits the given glyphs, laid out from the left, into lines.
+fn lines(fonts: &[ab_glyph::FontArc], glyphs: &[solstice_glyph::SectionGlyph]) -> Vec<Line> {
+    use ab_glyph::{Font, ScaleFont};
+
+    let mut lines: Vec<Line> = Vec::new();
+
+    for (i, section_glyph) in glyphs.iter().enumerate() {
+        let glyph = &section_glyph.glyph;
+        let font = fonts[section_glyph.font_id.0].as_scaled(glyph.scale);
+        let end = glyph.position.x + font.h_advance(glyph.id);
+
+        // Glyphs of a line share its baseline
+        match lines.last_mut() {
+            Some(line) if glyphs[line.glyphs.start].glyph.position.y == glyph.position.y => {
+                line.glyphs.end = i + 1;
+                line.width = line.width.max(end);
+            }
+            _ => lines.push(Line {
+                glyphs: i..i + 1,
+                width: end,
+            }),
+        }
+    }
+
+    lines
+}
+
+/// Computes the smallest rectangle containing the lines of the given glyphs.
+fn glyph_bounds(
+    fonts: &[ab_glyph::FontArc],
+    glyphs: &[solstice_glyph::SectionGlyph],
+) -> Option<ab_glyph::Rect> {
+    use ab_glyph::{Font, ScaleFont};
+
+    glyphs
+        .iter()
+        .map(|section_glyph| {
+            let glyph = &section_glyph.glyph;
+            let font = fonts[section_glyph.font_id.0].as_scaled(glyph.scale);
+
+            ab_glyph::Rect {
+                min: ab_glyph::point(glyph.position.x, glyph.position.y - font.ascent()),
+                max: ab_glyph::point(
+                    glyph.position.x + font.h_advance(glyph.id),
+                    glyph.position.y - font.descent(),
+                ),
+            }
+        })
+        .reduce(|a, b| ab_glyph::Rect {
+            min: ab_glyph::point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+            max: ab_glyph::point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The embedded font, covering Latin text, followed by the icon font,
+    /// covering icons only.
+    fn fonts() -> Vec<ab_glyph::FontArc> {
+        let icons = match font::ICONS {
+            iced_native::Font::External { bytes, .. } => bytes,
+            iced_native::Font::Default => unreachable!(),
+        };
+
+        [font::FALLBACK, icons]
+            .iter()
+            .map(|bytes| ab_glyph::FontArc::try_from_slice(bytes).expect("Load font"))
+            .collect()
+    }
+
+    fn spans(content: &str) -> Vec<(&str, usize)> {
+        let chain = [solstice_glyph::FontId(0), solstice_glyph::FontId(1)];
+
+        runs(&fonts(), &chain, content)
+            .iter()
+            .map(|run| (&content[run.start..run.end], run.font_id.0))
+            .collect()
+    }
+
+    #[test]
+    fn runs_switch_fonts_between_scripts() {
+        let content = format!("ok{}done", font::CHECKMARK_ICON);
+        let icon = font::CHECKMARK_ICON.to_string();
+
+        assert_eq!(spans(&content), vec![("ok", 0), (icon.as_str(), 1), ("done", 0)]);
+    }
+
+    #[test]
+    fn runs_keep_whitespace_in_the_previous_run() {
+        let content = format!("ok {} done", font::CHECKMARK_ICON);
+        let icon = format!("{} ", font::CHECKMARK_ICON);
+
+        assert_eq!(spans(&content), vec![("ok ", 0), (icon.as_str(), 1), ("done", 0)]);
+    }
+
+    #[test]
+    fn runs_draw_uncovered_characters_with_the_first_font() {
+        let content = format!("{}\u{10FFFD}ok", font::CHECKMARK_ICON);
+        let icon = font::CHECKMARK_ICON.to_string();
+
+        assert_eq!(spans(&content), vec![(icon.as_str(), 1), ("\u{10FFFD}ok", 0)]);
+    }
+}
@@ -0,0 +1,670 @@
+use crate::image::atlas::{self, Atlas};
+use crate::program;
+use crate::settings::ColorSpace;
+use crate::Transformation;
+use bytemuck::{Pod, Zeroable};
+use solstice::{
+    canvas::Canvas,
+    mesh::VertexMesh,
+    shader::{DynamicShader, RawUniformValue, UniformLocation},
+    vertex::Vertex,
+};
+use solstice_glyph::ab_glyph;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+const MAX_INSTANCES: usize = 1_000;
+
+/// The amount of atlas pages past which the atlas is cleared.
+const MAX_PAGES: usize = 4;
+
+#[derive(Copy, Clone, Debug, Vertex, Pod, Zeroable)]
+#[repr(C)]
+struct Position {
+    position: [f32; 2],
+}
+
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Instance {
+    position: [f32; 2],
+    size: [f32; 2],
+    texture_coordinates: [f32; 4],
+    alpha: f32,
+}
+
+impl solstice::vertex::Vertex for Instance {
+    fn build_bindings() -> &'static [solstice::vertex::VertexFormat] {
+        use solstice::vertex::{AttributeType, VertexFormat};
+        &[
+            VertexFormat {
+                name: "i_Pos",
+                offset: 0,
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Scale",
+                offset: std::mem::size_of::<[f32; 2]>(),
+                atype: AttributeType::F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_TexCoord",
+                offset: std::mem::size_of::<[f32; 4]>(),
+                atype: AttributeType::F32F32F32F32,
+                normalize: false,
+            },
+            VertexFormat {
+                name: "i_Alpha",
+                offset: std::mem::size_of::<[f32; 8]>(),
+                atype: AttributeType::F32,
+                normalize: false,
+            },
+        ]
+    }
+}
+
+/// Draws the glyphs of color fonts, which the glyph brush can only render
+/// as monochrome outlines.
+///
+/// Bitmap glyphs (`CBDT` and `sbix`) and layered glyphs (`COLR`) are
+/// rasterized into an RGBA atlas and drawn as textured quads over the text.
+/// Glyphs that cannot be rasterized, like bitmaps in a format other than PNG
+/// or glyphs larger than an atlas page, are drawn as outlines instead.
+#[derive(Debug)]
+pub struct Pipeline {
+    program: DynamicShader,
+    quad: VertexMesh<Position>,
+    instances: VertexMesh<Instance>,
+    transform_location: UniformLocation,
+    atlas: Atlas,
+    /// The bytes of every font and the index of its face, indexed by
+    /// `FontId`, since color tables are not exposed by `ab_glyph`.
+    sources: Vec<(Cow<'static, [u8]>, u32)>,
+    kinds: HashMap<(usize, u16), Kind>,
+    glyphs: HashMap<Key, Slot>,
+    queue: Vec<Queued>,
+}
+
+/// How a glyph is colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Outline,
+    Bitmap,
+    Layered,
+}
+
+/// A color glyph rasterized at a specific size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    font_id: usize,
+    glyph_id: u16,
+    scale: u32,
+    /// The color of the text, used by the layers painted with it.
+    foreground: [u8; 4],
+}
+
+/// The state of a color glyph at a specific size.
+#[derive(Debug)]
+enum Slot {
+    /// The glyph could not be rasterized, so it is drawn as an outline.
+    Outline,
+
+    /// The glyph is rasterized, but not in the atlas yet.
+    Raster(Raster),
+
+    /// The glyph is in the atlas.
+    Glyph(Glyph),
+}
+
+/// A rasterized color glyph in the atlas.
+#[derive(Debug, Clone, Copy)]
+struct Glyph {
+    entry: atlas::Entry,
+    /// The top left corner of the glyph relative to its origin, in physical
+    /// pixels.
+    offset: [f32; 2],
+    /// The size of the glyph, in physical pixels.
+    size: [f32; 2],
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Queued {
+    key: Key,
+    scale: ab_glyph::PxScale,
+    position: ab_glyph::Point,
+    alpha: f32,
+}
+
+impl Pipeline {
+    pub fn new(gl: &mut solstice::Context, color_space: ColorSpace) -> Self {
+        let program = {
+            const SRC: &str = include_str!("../shader/color_glyph.glsl");
+            program::create(gl, SRC, SRC, color_space)
+        };
+
+        let transform_location = program
+            .get_uniform_by_name("u_Transform")
+            .unwrap()
+            .location
+            .clone();
+        let atlas_location = program
+            .get_uniform_by_name("u_Atlas")
+            .unwrap()
+            .location
+            .clone();
+
+        gl.use_shader(Some(&program));
+        let matrix: [f32; 16] = Transformation::identity().into();
+        gl.set_uniform_by_location(&transform_location, &RawUniformValue::Mat4(matrix.into()));
+        gl.set_uniform_by_location(&atlas_location, &RawUniformValue::SignedInt(0));
+
+        let instances = VertexMesh::new(gl, MAX_INSTANCES).unwrap();
+        let quad = VertexMesh::with_data(
+            gl,
+            &[
+                Position { position: [0., 0.] },
+                Position { position: [0., 1.] },
+                Position { position: [1., 0.] },
+                Position { position: [1., 1.] },
+            ],
+        )
+        .unwrap();
+
+        Pipeline {
+            program,
+            quad,
+            instances,
+            transform_location,
+            atlas: Atlas::new(gl),
+            sources: Vec::new(),
+            kinds: HashMap::new(),
+            glyphs: HashMap::new(),
+            queue: Vec::new(),
+        }
+    }
+
+    /// Registers the bytes of the next font of the text pipeline, with the
+    /// index of its face.
+    pub fn add_font(&mut self, bytes: Cow<'static, [u8]>, index: u32) {
+        self.sources.push((bytes, index));
+    }
+
+    /// Queues the given glyph, positioned in physical pixels, if it is a
+    /// color glyph that can be rasterized.
+    ///
+    /// Returns `false` if the glyph must be drawn as an outline.
+    pub fn queue(
+        &mut self,
+        section_glyph: &solstice_glyph::SectionGlyph,
+        fonts: &[ab_glyph::FontArc],
+        foreground: [u8; 4],
+        alpha: f32,
+    ) -> bool {
+        let font_id = section_glyph.font_id.0;
+        let glyph = &section_glyph.glyph;
+        let sources = &self.sources;
+
+        let kind = *self
+            .kinds
+            .entry((font_id, glyph.id.0))
+            .or_insert_with(|| {
+                let (bytes, index) = &sources[font_id];
+
+                kind(bytes, *index, glyph.id.0)
+            });
+
+        if kind == Kind::Outline {
+            return false;
+        }
+
+        let queued = Queued {
+            key: Key {
+                font_id,
+                glyph_id: glyph.id.0,
+                scale: glyph.scale.y.to_bits(),
+                // Only layers take the color of the text
+                foreground: if kind == Kind::Layered {
+                    foreground
+                } else {
+                    [0; 4]
+                },
+            },
+            scale: glyph.scale,
+            position: glyph.position,
+            alpha,
+        };
+
+        // Glyphs are rasterized right away, so the ones that fail can still
+        // be drawn as outlines
+        let slot = self
+            .glyphs
+            .entry(queued.key)
+            .or_insert_with(|| {
+                let (bytes, index) = &sources[font_id];
+
+                rasterize(bytes, *index, &fonts[font_id], kind, &queued)
+            });
+
+        if let Slot::Outline = slot {
+            return false;
+        }
+
+        self.queue.push(queued);
+
+        true
+    }
+
+    /// Draws the queued glyphs, rasterizing the ones missing from the atlas.
+    pub fn draw(
+        &mut self,
+        gl: &mut solstice::Context,
+        target: Option<&Canvas>,
+        fonts: &[ab_glyph::FontArc],
+        transformation: Transformation,
+        scissor: solstice::viewport::Viewport<i32>,
+    ) {
+        if self.queue.is_empty() {
+            return;
+        }
+
+        // Start over with an empty atlas once it has grown too much, before
+        // any glyph of this frame is placed in it
+        if self.atlas.pages() > MAX_PAGES {
+            log::debug!("Color glyph atlas has too many pages, clearing it");
+
+            self.atlas = Atlas::new(gl);
+            self.glyphs.retain(|_, slot| !matches!(slot, Slot::Glyph(_)));
+        }
+
+        let mut instances = Vec::with_capacity(self.queue.len());
+
+        for queued in std::mem::take(&mut self.queue) {
+            let sources = &self.sources;
+            let kind = self.kinds[&(queued.key.font_id, queued.key.glyph_id)];
+
+            // Glyphs dropped with the atlas are rasterized again
+            let slot = self.glyphs.entry(queued.key).or_insert_with(|| {
+                let (bytes, index) = &sources[queued.key.font_id];
+
+                rasterize(bytes, *index, &fonts[queued.key.font_id], kind, &queued)
+            });
+
+            if let Slot::Raster(raster) = slot {
+                *slot = match self.atlas.upload(gl, raster.width, raster.height, &raster.pixels) {
+                    Some(entry) => Slot::Glyph(Glyph {
+                        entry,
+                        offset: raster.offset,
+                        size: raster.size,
+                    }),
+                    None => Slot::Outline,
+                };
+            }
+
+            if let Slot::Glyph(glyph) = slot {
+                instances.push((
+                    glyph.entry.page,
+                    Instance {
+                        position: [
+                            queued.position.x + glyph.offset[0],
+                            queued.position.y + glyph.offset[1],
+                        ],
+                        size: glyph.size,
+                        texture_coordinates: glyph.entry.texture_coordinates(),
+                        alpha: queued.alpha,
+                    },
+                ));
+            }
+        }
+
+        if instances.is_empty() {
+            return;
+        }
+
+        gl.use_shader(Some(&self.program));
+
+        let matrix: [f32; 16] = transformation.into();
+        gl.set_uniform_by_location(
+            &self.transform_location,
+            &RawUniformValue::Mat4(matrix.into()),
+        );
+
+        let Pipeline {
+            program: shader,
+            quad,
+            instances: buffer,
+            atlas,
+            ..
+        } = self;
+
+        // Glyphs are drawn in order, in runs sharing an atlas page
+        atlas::draw_runs(gl, atlas, &instances, |gl, run| {
+            for chunk in run.chunks(MAX_INSTANCES) {
+                buffer.set_vertices(gl, chunk, 0);
+
+                use solstice::mesh::MeshAttacher;
+                let attached = quad.attach_with_step(&*buffer, 1);
+
+                let geometry = solstice::Geometry {
+                    mesh: attached,
+                    draw_range: 0..4,
+                    draw_mode: solstice::DrawMode::TriangleStrip,
+                    instance_count: chunk.len() as _,
+                };
+                solstice::Renderer::draw(
+                    gl,
+                    &*shader,
+                    &geometry,
+                    solstice::PipelineSettings {
+                        depth_state: None,
+                        blend_state: Some(program::blend_state()),
+                        scissor_state: Some(scissor),
+                        framebuffer: target,
+                        ..Default::default()
+                    },
+                );
+            }
+        });
+    }
+}
+
+/// Rasterizes the queued glyph, or returns [`Slot::Outline`] if it cannot
+/// be.
+fn rasterize(
+    source: &[u8],
+    index: u32,
+    font: &ab_glyph::FontArc,
+    kind: Kind,
+    queued: &Queued,
+) -> Slot {
+    let face = match ttf_parser::Face::parse(source, index) {
+        Ok(face) => face,
+        Err(_) => return Slot::Outline,
+    };
+
+    let glyph_id = ttf_parser::GlyphId(queued.key.glyph_id);
+
+    let raster = match kind {
+        Kind::Bitmap => rasterize_bitmap(&face, glyph_id, queued.scale),
+        Kind::Layered => rasterize_layers(
+            &face,
+            font,
+            glyph_id,
+            queued.scale,
+            queued.key.foreground,
+        ),
+        Kind::Outline => None,
+    };
+
+    match raster {
+        Some(raster) if raster.width.max(raster.height) < atlas::SIZE => Slot::Raster(raster),
+        _ => Slot::Outline,
+    }
+}
+
+/// The RGBA pixels of a rasterized glyph.
+#[derive(Debug)]
+struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    /// The top left corner of the glyph relative to its origin, in physical
+    /// pixels.
+    offset: [f32; 2],
+    /// The size the pixels are drawn at, in physical pixels.
+    size: [f32; 2],
+}
+
+fn kind(source: &[u8], index: u32, glyph_id: u16) -> Kind {
+    let face = match ttf_parser::Face::parse(source, index) {
+        Ok(face) => face,
+        Err(_) => return Kind::Outline,
+    };
+
+    let glyph_id = ttf_parser::GlyphId(glyph_id);
+
+    if face.glyph_raster_image(glyph_id, u16::MAX).is_some() {
+        Kind::Bitmap
+    } else if face.is_color_glyph(glyph_id) {
+        Kind::Layered
+    } else {
+        Kind::Outline
+    }
+}
+
+/// Returns the size of the em square of the face at the given scale.
+///
+/// Like `ab_glyph`, scales are relative to the height of the face.
+fn pixels_per_em(face: &ttf_parser::Face<'_>, scale: ab_glyph::PxScale) -> f32 {
+    let height = f32::from(face.ascender()) - f32::from(face.descender());
+
+    scale.y * f32::from(face.units_per_em()) / height
+}
+
+/// Decodes the bitmap strike closest to the given scale, which is then
+/// scaled by the GPU.
+fn rasterize_bitmap(
+    face: &ttf_parser::Face<'_>,
+    glyph_id: ttf_parser::GlyphId,
+    scale: ab_glyph::PxScale,
+) -> Option<Raster> {
+    let pixels_per_em = pixels_per_em(face, scale);
+    let image = face.glyph_raster_image(glyph_id, pixels_per_em.round() as u16)?;
+
+    if image.format != ttf_parser::RasterImageFormat::PNG {
+        return None;
+    }
+
+    let pixels = image_rs::load_from_memory_with_format(image.data, image_rs::ImageFormat::Png)
+        .ok()?
+        .to_rgba8();
+
+    let (width, height) = pixels.dimensions();
+    let factor = pixels_per_em / f32::from(image.pixels_per_em);
+
+    // The offset of the image is the position of its bottom left corner,
+    // with the y axis pointing up
+    let offset = [
+        f32::from(image.x) * factor,
+        -(f32::from(image.y) + f32::from(image.height)) * factor,
+    ];
+
+    Some(Raster {
+        width,
+        height,
+        pixels: pixels.into_raw(),
+        offset,
+        size: [width as f32 * factor, height as f32 * factor],
+    })
+}
+
+/// Composites the colored layers of the glyph, from bottom to top.
+fn rasterize_layers(
+    face: &ttf_parser::Face<'_>,
+    font: &ab_glyph::FontArc,
+    glyph_id: ttf_parser::GlyphId,
+    scale: ab_glyph::PxScale,
+    foreground: [u8; 4],
+) -> Option<Raster> {
+    use ab_glyph::Font;
+
+    let mut painter = Layers {
+        foreground,
+        outline: None,
+        layers: Vec::new(),
+    };
+
+    face.paint_color_glyph(glyph_id, 0, &mut painter)?;
+
+    let layers: Vec<_> = painter
+        .layers
+        .into_iter()
+        .filter_map(|(glyph_id, color)| {
+            let glyph = ab_glyph::GlyphId(glyph_id.0).with_scale(scale);
+
+            font.outline_glyph(glyph).map(|outline| (outline, color))
+        })
+        .collect();
+
+    let bounds = layers
+        .iter()
+        .map(|(outline, _)| outline.px_bounds())
+        .reduce(|a, b| ab_glyph::Rect {
+            min: ab_glyph::point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+            max: ab_glyph::point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+        })?;
+
+    let width = bounds.width().ceil() as u32;
+    let height = bounds.height().ceil() as u32;
+    let mut pixels = vec![0; (width * height * 4) as usize];
+
+    for (outline, color) in &layers {
+        let layer = outline.px_bounds();
+        let left = (layer.min.x - bounds.min.x) as u32;
+        let top = (layer.min.y - bounds.min.y) as u32;
+
+        outline.draw(|x, y, coverage| {
+            let (x, y) = (left + x, top + y);
+
+            if x < width && y < height {
+                let index = ((y * width + x) * 4) as usize;
+
+                blend(&mut pixels[index..index + 4], *color, coverage);
+            }
+        });
+    }
+
+    Some(Raster {
+        width,
+        height,
+        pixels,
+        offset: [bounds.min.x, bounds.min.y],
+        size: [width as f32, height as f32],
+    })
+}
+
+/// Blends a color over a pixel with straight alpha.
+fn blend(pixel: &mut [u8], color: [u8; 4], coverage: f32) {
+    let source_alpha = f32::from(color[3]) / 255.0 * coverage;
+    let target_alpha = f32::from(pixel[3]) / 255.0;
+    let alpha = source_alpha + target_alpha * (1.0 - source_alpha);
+
+    if alpha <= 0.0 {
+        return;
+    }
+
+    for (target, source) in pixel.iter_mut().zip(color).take(3) {
+        let blended = (f32::from(source) * source_alpha
+            + f32::from(*target) * target_alpha * (1.0 - source_alpha))
+            / alpha;
+
+        *target = blended.round() as u8;
+    }
+
+    pixel[3] = (alpha * 255.0).round() as u8;
+}
+
+/// Collects the layers of a `COLR` glyph with their colors.
+struct Layers {
+    foreground: [u8; 4],
+    outline: Option<ttf_parser::GlyphId>,
+    layers: Vec<(ttf_parser::GlyphId, [u8; 4])>,
+}
+
+impl ttf_parser::colr::Painter for Layers {
+    fn outline(&mut self, glyph_id: ttf_parser::GlyphId) {
+        self.outline = Some(glyph_id);
+    }
+
+    fn paint_foreground(&mut self) {
+        if let Some(glyph_id) = self.outline.take() {
+            self.layers.push((glyph_id, self.foreground));
+        }
+    }
+
+    fn paint_color(&mut self, color: ttf_parser::RgbaColor) {
+        if let Some(glyph_id) = self.outline.take() {
+            self.layers
+                .push((glyph_id, [color.red, color.green, color.blue, color.alpha]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A font of three glyphs without outlines, where glyph `1` is a `COLR`
+    /// glyph painting glyph `2` in red, then in the color of the text.
+    ///
+    /// Its em square is 1000 units tall, half of its height.
+    const COLR: &[u8] = include_bytes!("../../tests/fixtures/colr.ttf");
+
+    #[test]
+    fn kind_tells_layered_glyphs_from_outlines() {
+        assert_eq!(kind(COLR, 0, 1), Kind::Layered);
+        assert_eq!(kind(COLR, 0, 2), Kind::Outline);
+        assert_eq!(kind(b"not a font", 0, 1), Kind::Outline);
+    }
+
+    #[test]
+    fn layers_are_painted_with_the_palette_and_the_foreground() {
+        let face = ttf_parser::Face::parse(COLR, 0).expect("Parse fixture");
+        let foreground = [10, 20, 30, 255];
+
+        let mut painter = Layers {
+            foreground,
+            outline: None,
+            layers: Vec::new(),
+        };
+
+        face.paint_color_glyph(ttf_parser::GlyphId(1), 0, &mut painter)
+            .expect("Paint color glyph");
+
+        assert_eq!(
+            painter.layers,
+            vec![
+                (ttf_parser::GlyphId(2), [255, 0, 0, 255]),
+                (ttf_parser::GlyphId(2), foreground),
+            ]
+        );
+    }
+
+    #[test]
+    fn pixels_per_em_is_relative_to_the_height_of_the_face() {
+        let face = ttf_parser::Face::parse(COLR, 0).expect("Parse fixture");
+
+        assert_eq!(pixels_per_em(&face, ab_glyph::PxScale::from(20.0)), 10.0);
+        assert_eq!(pixels_per_em(&face, ab_glyph::PxScale::from(64.0)), 32.0);
+    }
+
+    #[test]
+    fn blend_covers_transparent_pixels() {
+        let mut pixel = [0, 0, 0, 0];
+        blend(&mut pixel, [200, 100, 50, 255], 1.0);
+
+        assert_eq!(pixel, [200, 100, 50, 255]);
+    }
+
+    #[test]
+    fn blend_mixes_partial_coverage() {
+        let mut pixel = [0, 0, 255, 255];
+        blend(&mut pixel, [255, 0, 0, 255], 0.5);
+
+        assert_eq!(pixel, [128, 0, 128, 255]);
+    }
+
+    #[test]
+    fn blend_ignores_zero_coverage() {
+        let mut pixel = [0, 0, 0, 0];
+        blend(&mut pixel, [255, 255, 255, 255], 0.0);
+
+        assert_eq!(pixel, [0, 0, 0, 0]);
+
+        let mut pixel = [1, 2, 3, 4];
+        blend(&mut pixel, [255, 255, 255, 255], 0.0);
+
+        assert_eq!(pixel, [1, 2, 3, 4]);
+    }
+}
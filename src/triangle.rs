@@ -1,9 +1,15 @@
 //! Draw meshes of triangles.
+mod msaa;
+
 use crate::program;
+use crate::settings;
 use crate::Transformation;
 use iced_graphics::layer;
+use iced_native::{Point, Rectangle, Size};
+use std::collections::HashMap;
 
 pub use iced_graphics::triangle::{Mesh2D, Vertex2D};
+use solstice::canvas::Canvas;
 use solstice::mesh::IndexedMesh;
 use solstice::shader::{DynamicShader, RawUniformValue, UniformLocation};
 use solstice::vertex::VertexFormat;
@@ -34,22 +40,39 @@ impl solstice::vertex::Vertex for Vertex {
     }
 }
 
-const VERTEX_BUFFER_SIZE: usize = 10_000;
-const INDEX_BUFFER_SIZE: usize = 10_000;
+const INITIAL_VERTICES: usize = 10_000;
+const INITIAL_INDICES: usize = 10_000;
+
+/// The largest buffers uploaded at once. Layers with more geometry are drawn
+/// in several batches.
+///
+/// These only bound the memory of the buffers, at 24 MB of vertices and 12 MB
+/// of indices. `GL_MAX_ELEMENTS_VERTICES` and `GL_MAX_ELEMENTS_INDICES` are not
+/// limits: they are performance hints for `glDrawRangeElements`, which is not
+/// used here, and `glDrawElements` accepts any count the buffers can hold.
+const MAX_VERTICES: usize = 1_000_000;
+const MAX_INDICES: usize = 3_000_000;
 
 #[derive(Debug)]
 pub(crate) struct Pipeline {
     program: DynamicShader,
     mesh: IndexedMesh<Vertex, u32>,
+    vertex_capacity: usize,
+    index_capacity: usize,
     transform_location: UniformLocation,
     current_transform: Transformation,
+    blit: Option<msaa::Blit>,
 }
 
 impl Pipeline {
-    pub fn new(gl: &mut solstice::Context) -> Pipeline {
+    pub fn new(
+        gl: &mut solstice::Context,
+        antialiasing: Option<settings::Antialiasing>,
+        color_space: settings::ColorSpace,
+    ) -> Pipeline {
         let program = {
             const SRC: &str = include_str!("shader/triangle.glsl");
-            program::create(gl, SRC, SRC)
+            program::create(gl, SRC, SRC, color_space)
         };
 
         let transform_location = program
@@ -65,78 +88,166 @@ impl Pipeline {
             &RawUniformValue::Mat4(transform.into()),
         );
 
-        let mesh = IndexedMesh::new(gl, VERTEX_BUFFER_SIZE, INDEX_BUFFER_SIZE).unwrap();
+        let mesh = IndexedMesh::new(gl, INITIAL_VERTICES, INITIAL_INDICES).unwrap();
 
         Pipeline {
             program,
             mesh,
+            vertex_capacity: INITIAL_VERTICES,
+            index_capacity: INITIAL_INDICES,
             transform_location,
             current_transform: Transformation::identity(),
+            blit: antialiasing.map(|a| msaa::Blit::new(gl, a, color_space)),
         }
     }
 
     pub fn draw(
         &mut self,
         gl: &mut solstice::Context,
-        target_height: u32,
+        target: Option<&Canvas>,
+        target_size: Size<u32>,
         transformation: Transformation,
         scale_factor: f32,
         meshes: &[layer::Mesh<'_>],
     ) {
+        let target_height = target_size.height;
+
+        // We merge consecutive meshes sharing clip bounds into a single piece,
+        // baking their origin into the vertices
+        let mut pieces: Vec<Piece> = Vec::new();
+
+        for mesh in meshes {
+            match pieces.last_mut() {
+                Some(last) if last.can_append(mesh) => last.append(mesh),
+                _ => pieces.extend(Piece::split(mesh)),
+            }
+        }
+
+        // When multisampling, we draw every mesh into an offscreen target
+        // first, only touching the region the meshes are clipped to
+        let mut blit = self.blit.take();
+        let mesh_target = match &mut blit {
+            Some(blit) => {
+                let region = pieces
+                    .iter()
+                    .map(|piece| piece.clip_bounds)
+                    .reduce(|a, b| a.union(&b))
+                    .map(|bounds| scissor(bounds, scale_factor, target_height))
+                    .unwrap_or_else(|| solstice::viewport::Viewport::new(0, 0, 0, 0));
+
+                Some(blit.targets(gl, target_size.width, target_size.height, region))
+            }
+            None => target,
+        };
+
+        // Then we group as many pieces as the buffers can hold into a batch
+        let mut batch_start = 0;
+        let mut batch_vertices = 0;
+        let mut batch_indices = 0;
+
+        for (i, piece) in pieces.iter().enumerate() {
+            if batch_vertices + piece.vertices.len() > MAX_VERTICES
+                || batch_indices + piece.indices.len() > MAX_INDICES
+            {
+                self.draw_batch(
+                    gl,
+                    mesh_target,
+                    target_height,
+                    transformation,
+                    scale_factor,
+                    &pieces[batch_start..i],
+                );
+
+                batch_start = i;
+                batch_vertices = 0;
+                batch_indices = 0;
+            }
+
+            batch_vertices += piece.vertices.len();
+            batch_indices += piece.indices.len();
+        }
+
+        self.draw_batch(
+            gl,
+            mesh_target,
+            target_height,
+            transformation,
+            scale_factor,
+            &pieces[batch_start..],
+        );
+
+        if let Some(blit) = &mut blit {
+            blit.draw(gl, target);
+        }
+
+        self.blit = blit;
+    }
+
+    fn draw_batch(
+        &mut self,
+        gl: &mut solstice::Context,
+        target: Option<&Canvas>,
+        target_height: u32,
+        transformation: Transformation,
+        scale_factor: f32,
+        pieces: &[Piece],
+    ) {
+        if pieces.is_empty() {
+            return;
+        }
+
+        let total_vertices = pieces.iter().map(|piece| piece.vertices.len()).sum();
+        let total_indices = pieces.iter().map(|piece| piece.indices.len()).sum();
+
+        self.reserve(gl, total_vertices, total_indices);
+
         // We upload all the vertices and indices upfront
         let mut last_vertex = 0;
         let mut last_index = 0;
 
         let mut index_scratch = Vec::new();
 
-        for layer::Mesh { buffers, .. } in meshes {
-            let vertices = bytemuck::cast_slice(buffers.vertices.as_slice());
+        for piece in pieces {
+            let vertices = bytemuck::cast_slice(&piece.vertices);
 
             index_scratch.clear();
-            for index in buffers.indices.iter() {
+            for index in piece.indices.iter() {
                 index_scratch.push(index + last_vertex as u32)
             }
 
             self.mesh.set_vertices(gl, vertices, last_vertex);
             self.mesh.set_indices(gl, &index_scratch, last_index);
 
-            last_vertex += buffers.vertices.len();
-            last_index += buffers.indices.len();
+            last_vertex += piece.vertices.len();
+            last_index += piece.indices.len();
         }
 
-        // Then we draw each mesh using offsets
+        if self.current_transform != transformation {
+            gl.use_shader(Some(&self.program));
+            let matrix: [f32; 16] = transformation.into();
+            gl.set_uniform_by_location(
+                &self.transform_location,
+                &RawUniformValue::Mat4(matrix.into()),
+            );
+
+            self.current_transform = transformation;
+        }
+
+        // Then we draw each piece using offsets
         let mut last_index = 0;
 
-        for layer::Mesh {
-            buffers,
-            origin,
+        for Piece {
+            indices,
             clip_bounds,
-        } in meshes
+            ..
+        } in pieces
         {
-            let transform = transformation * Transformation::translate(origin.x, origin.y);
-            if self.current_transform != transform {
-                gl.use_shader(Some(&self.program));
-                let matrix: [f32; 16] = transform.into();
-                gl.set_uniform_by_location(
-                    &self.transform_location,
-                    &RawUniformValue::Mat4(matrix.into()),
-                );
-
-                self.current_transform = transform;
-            }
-
-            let clip_bounds = (*clip_bounds * scale_factor).snap();
-            let scissor = solstice::viewport::Viewport::new(
-                clip_bounds.x as i32,
-                (target_height - (clip_bounds.y + clip_bounds.height)) as i32,
-                clip_bounds.width as i32,
-                clip_bounds.height as i32,
-            );
+            let scissor = scissor(*clip_bounds, scale_factor, target_height);
 
             let offset = last_index * std::mem::size_of::<u32>();
             let geometry = solstice::Geometry {
                 mesh: &self.mesh,
-                draw_range: offset..(offset + buffers.indices.len()),
+                draw_range: offset..(offset + indices.len()),
                 draw_mode: solstice::DrawMode::Triangles,
                 instance_count: 1,
             };
@@ -148,12 +259,149 @@ impl Pipeline {
                 solstice::PipelineSettings {
                     polygon_state: Default::default(),
                     depth_state: None,
+                    blend_state: Some(program::premultiplied_blend_state()),
                     scissor_state: Some(scissor),
+                    framebuffer: target,
                     ..Default::default()
                 },
             );
 
-            last_index += buffers.indices.len();
+            last_index += indices.len();
+        }
+    }
+
+    /// Grows the buffers geometrically until they fit the given geometry.
+    fn reserve(&mut self, gl: &mut solstice::Context, vertices: usize, indices: usize) {
+        if vertices <= self.vertex_capacity && indices <= self.index_capacity {
+            return;
+        }
+
+        let grow = |mut capacity: usize, amount: usize, max: usize| {
+            while capacity < amount {
+                capacity *= 2;
+            }
+
+            capacity.min(max)
+        };
+
+        self.vertex_capacity = grow(self.vertex_capacity, vertices, MAX_VERTICES);
+        self.index_capacity = grow(self.index_capacity, indices, MAX_INDICES);
+
+        log::debug!(
+            "Resizing triangle buffers to {} vertices and {} indices",
+            self.vertex_capacity,
+            self.index_capacity
+        );
+
+        self.mesh = IndexedMesh::new(gl, self.vertex_capacity, self.index_capacity).unwrap();
+    }
+}
+
+/// Returns the scissor rectangle of the given logical clip bounds.
+fn scissor(
+    clip_bounds: Rectangle<f32>,
+    scale_factor: f32,
+    target_height: u32,
+) -> solstice::viewport::Viewport<i32> {
+    let clip_bounds = (clip_bounds * scale_factor).snap();
+    let height = clip_bounds.height.min(target_height.saturating_sub(clip_bounds.y));
+
+    solstice::viewport::Viewport::new(
+        clip_bounds.x as i32,
+        target_height.saturating_sub(clip_bounds.y + height) as i32,
+        clip_bounds.width as i32,
+        height as i32,
+    )
+}
+
+/// The geometry of one or more [`layer::Mesh`]es sharing the same clip
+/// bounds, translated by their origin and drawn in a single call.
+#[derive(Debug)]
+struct Piece {
+    vertices: Vec<Vertex2D>,
+    indices: Vec<u32>,
+    clip_bounds: Rectangle<f32>,
+}
+
+impl Piece {
+    /// Splits a mesh into pieces fitting the maximum buffer sizes.
+    ///
+    /// Meshes within the limits become a single piece. Larger ones are split
+    /// triangle by triangle, copying the vertices each piece uses.
+    fn split(mesh: &layer::Mesh<'_>) -> Vec<Piece> {
+        let layer::Mesh {
+            buffers,
+            origin,
+            clip_bounds,
+        } = mesh;
+
+        let empty = || Piece {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            clip_bounds: *clip_bounds,
+        };
+
+        if buffers.vertices.len() <= MAX_VERTICES && buffers.indices.len() <= MAX_INDICES {
+            let mut piece = empty();
+            piece.append(mesh);
+
+            return vec![piece];
+        }
+
+        let mut pieces = Vec::new();
+        let mut piece = empty();
+        let mut remap = HashMap::new();
+
+        for triangle in buffers.indices.chunks_exact(3) {
+            if piece.vertices.len() + 3 > MAX_VERTICES || piece.indices.len() + 3 > MAX_INDICES {
+                pieces.push(std::mem::replace(&mut piece, empty()));
+
+                remap.clear();
+            }
+
+            for index in triangle {
+                let vertices = &mut piece.vertices;
+                let remapped = *remap.entry(*index).or_insert_with(|| {
+                    vertices.push(translate(buffers.vertices[*index as usize], *origin));
+
+                    (vertices.len() - 1) as u32
+                });
+
+                piece.indices.push(remapped);
+            }
         }
+
+        if !piece.indices.is_empty() {
+            pieces.push(piece);
+        }
+
+        pieces
+    }
+
+    /// Returns whether the given mesh can be merged into this [`Piece`].
+    fn can_append(&self, mesh: &layer::Mesh<'_>) -> bool {
+        self.clip_bounds == mesh.clip_bounds
+            && self.vertices.len() + mesh.buffers.vertices.len() <= MAX_VERTICES
+            && self.indices.len() + mesh.buffers.indices.len() <= MAX_INDICES
+    }
+
+    fn append(&mut self, mesh: &layer::Mesh<'_>) {
+        let base = self.vertices.len() as u32;
+
+        self.vertices.extend(
+            mesh.buffers
+                .vertices
+                .iter()
+                .map(|vertex| translate(*vertex, mesh.origin)),
+        );
+        self.indices
+            .extend(mesh.buffers.indices.iter().map(|index| index + base));
+    }
+}
+
+fn translate(vertex: Vertex2D, origin: Point) -> Vertex2D {
+    Vertex2D {
+        position: [vertex.position[0] + origin.x, vertex.position[1] + origin.y],
+        ..vertex
     }
 }
@@ -0,0 +1,181 @@
+use crate::program;
+use crate::settings;
+use bytemuck::{Pod, Zeroable};
+use solstice::canvas::Canvas;
+use solstice::mesh::VertexMesh;
+use solstice::shader::{DynamicShader, RawUniformValue};
+use solstice::texture::{FilterMode, Texture};
+use solstice::vertex::Vertex;
+
+#[derive(Copy, Clone, Debug, Vertex, Pod, Zeroable)]
+#[repr(C)]
+struct Position {
+    position: [f32; 2],
+}
+
+/// Renders into a multisampled offscreen target and blends the resolved
+/// result back into the current framebuffer.
+///
+/// The target holds premultiplied colors and only the region meshes are
+/// clipped to is cleared, resolved and blended.
+#[derive(Debug)]
+pub struct Blit {
+    program: DynamicShader,
+    quad: VertexMesh<Position>,
+    sample_count: u32,
+    targets: Option<Targets>,
+    region: solstice::viewport::Viewport<i32>,
+}
+
+#[derive(Debug)]
+struct Targets {
+    attachment: Canvas,
+    resolve: Canvas,
+    width: u32,
+    height: u32,
+}
+
+impl Blit {
+    pub fn new(
+        gl: &mut solstice::Context,
+        antialiasing: settings::Antialiasing,
+        color_space: settings::ColorSpace,
+    ) -> Blit {
+        let program = {
+            const SRC: &str = include_str!("../shader/blit.glsl");
+            program::create(gl, SRC, SRC, color_space)
+        };
+
+        let texture_location = program
+            .get_uniform_by_name("u_Texture")
+            .unwrap()
+            .location
+            .clone();
+
+        gl.use_shader(Some(&program));
+        gl.set_uniform_by_location(&texture_location, &RawUniformValue::SignedInt(0));
+
+        let quad = VertexMesh::with_data(
+            gl,
+            &[
+                Position { position: [0., 0.] },
+                Position { position: [0., 1.] },
+                Position { position: [1., 0.] },
+                Position { position: [1., 1.] },
+            ],
+        )
+        .unwrap();
+
+        Blit {
+            program,
+            quad,
+            sample_count: antialiasing.sample_count(),
+            targets: None,
+            region: solstice::viewport::Viewport::new(0, 0, 0, 0),
+        }
+    }
+
+    /// Returns the multisampled target with the given region cleared,
+    /// recreating it if the size of the viewport changed.
+    pub fn targets(
+        &mut self,
+        gl: &mut solstice::Context,
+        width: u32,
+        height: u32,
+        region: solstice::viewport::Viewport<i32>,
+    ) -> &Canvas {
+        let recreate = match &self.targets {
+            Some(targets) => targets.width != width || targets.height != height,
+            None => true,
+        };
+
+        if recreate {
+            let attachment = Canvas::new(
+                gl,
+                solstice::canvas::Settings {
+                    width: width as _,
+                    height: height as _,
+                    msaa: self.sample_count as _,
+                    ..Default::default()
+                },
+            )
+            .expect("Create multisampled target");
+
+            let resolve = Canvas::new(
+                gl,
+                solstice::canvas::Settings {
+                    width: width as _,
+                    height: height as _,
+                    ..Default::default()
+                },
+            )
+            .expect("Create resolve target");
+
+            self.targets = Some(Targets {
+                attachment,
+                resolve,
+                width,
+                height,
+            });
+        }
+
+        self.region = region;
+        let targets = self.targets.as_ref().unwrap();
+
+        solstice::Renderer::clear(
+            gl,
+            solstice::ClearSettings {
+                color: Some(solstice::Color::new(0.0, 0.0, 0.0, 0.0).into()),
+                target: Some(&targets.attachment),
+                scissor: Some(region),
+                ..Default::default()
+            },
+        );
+
+        &targets.attachment
+    }
+
+    /// Resolves the multisampled target and blends it into the given
+    /// framebuffer.
+    pub fn draw(&mut self, gl: &mut solstice::Context, target: Option<&Canvas>) {
+        let targets = match &self.targets {
+            Some(targets) => targets,
+            None => return,
+        };
+
+        gl.blit_framebuffer(
+            Some(&targets.attachment),
+            Some(&targets.resolve),
+            self.region,
+            self.region,
+            FilterMode::Nearest,
+        );
+
+        gl.use_shader(Some(&self.program));
+        gl.bind_texture_to_unit(
+            targets.resolve.get_texture_type(),
+            targets.resolve.get_texture_key(),
+            0.into(),
+        );
+
+        let geometry = solstice::Geometry {
+            mesh: &self.quad,
+            draw_range: 0..4,
+            draw_mode: solstice::DrawMode::TriangleStrip,
+            instance_count: 1,
+        };
+
+        solstice::Renderer::draw(
+            gl,
+            &self.program,
+            &geometry,
+            solstice::PipelineSettings {
+                depth_state: None,
+                blend_state: Some(program::premultiplied_blend_state()),
+                scissor_state: Some(self.region),
+                framebuffer: target,
+                ..Default::default()
+            },
+        );
+    }
+}
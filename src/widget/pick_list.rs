@@ -0,0 +1,11 @@
+//! Display a dropdown list of selectable values.
+use crate::Renderer;
+
+pub use iced_graphics::overlay::menu::Style as Menu;
+pub use iced_graphics::pick_list::{Style, StyleSheet};
+pub use iced_native::widget::pick_list::State;
+
+/// A widget allowing the selection of a single value from a list of options.
+///
+/// This is an alias of an `iced_native` pick list with an `iced_solstice::Renderer`.
+pub type PickList<'a, T, Message> = iced_native::widget::PickList<'a, T, Message, Renderer>;
@@ -0,0 +1,8 @@
+//! Display a horizontal or vertical rule for dividing content.
+pub use iced_graphics::rule::{FillMode, Style, StyleSheet};
+
+/// Display a horizontal or vertical rule for dividing content.
+///
+/// This is an alias of an `iced_native` rule used by an
+/// `iced_solstice::Renderer`.
+pub type Rule<'a> = iced_native::widget::Rule<'a>;
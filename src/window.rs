@@ -2,3 +2,37 @@
 mod compositor;
 
 pub use compositor::Compositor;
+
+use crate::settings::{ColorSpace, Settings};
+use solstice::glow::{self, HasContext};
+
+/// Sets up the global GL state every pipeline of a [`Backend`] expects.
+///
+/// The runners of this crate call it when creating their context. Hosts
+/// embedding an [`Overlay`] in their own context must call it with the
+/// [`Settings`] of the overlay before drawing it, and again whenever they
+/// change the blending or sRGB state themselves.
+///
+/// # Safety
+/// The given context must be current on the calling thread.
+///
+/// [`Backend`]: crate::Backend
+/// [`Overlay`]: crate::Overlay
+pub unsafe fn configure(gl: &glow::Context, settings: &Settings) {
+    // Enable auto-conversion from/to sRGB only when blending in linear space
+    match settings.color_space {
+        ColorSpace::Srgb => gl.enable(glow::FRAMEBUFFER_SRGB),
+        ColorSpace::LegacyGamma => gl.disable(glow::FRAMEBUFFER_SRGB),
+    }
+
+    // Enable alpha blending
+    gl.enable(glow::BLEND);
+    gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+
+    // Only multisample when the context was created for it
+    if settings.antialiasing.is_some() {
+        gl.enable(glow::MULTISAMPLE);
+    } else {
+        gl.disable(glow::MULTISAMPLE);
+    }
+}
@@ -1,13 +1,21 @@
+use crate::settings::ColorSpace;
+use crate::window;
 use crate::{Backend, Color, Error, Renderer, Settings, Viewport};
 
 use core::ffi::c_void;
 use iced_graphics::{Antialiasing, Size};
-use iced_native::mouse;
 
-/// A window graphics backend for iced powered by `glow`.
+/// A window graphics backend for iced powered by `solstice`.
+///
+/// The [`mouse::Interaction`] of a frame is tracked by the
+/// [`iced_native::program::State`] driving the [`Renderer`], so the
+/// compositor only needs to present its primitives.
+///
+/// [`mouse::Interaction`]: iced_native::mouse::Interaction
 #[allow(missing_debug_implementations)]
 pub struct Compositor {
     ctx: solstice::Context,
+    color_space: ColorSpace,
 }
 
 impl iced_graphics::window::GLCompositor for Compositor {
@@ -19,21 +27,17 @@ impl iced_graphics::window::GLCompositor for Compositor {
         loader_function: impl FnMut(&str) -> *const c_void,
     ) -> Result<(Self, Self::Renderer), Error> {
         let gl = solstice::glow::Context::from_loader_function(loader_function);
-        let mut ctx = solstice::Context::new(gl);
+        window::configure(&gl, &settings);
 
-        // // Enable auto-conversion from/to sRGB
-        // gl.enable(glow::FRAMEBUFFER_SRGB);
-        //
-        // // Enable alpha blending
-        // gl.enable(glow::BLEND);
-        // gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
-        //
-        // // Disable multisampling by default
-        // gl.disable(glow::MULTISAMPLE);
+        let mut ctx = solstice::Context::new(gl);
 
+        let color_space = settings.color_space;
         let renderer = Renderer::new(Backend::new(&mut ctx, settings));
 
-        Ok((Self { ctx }, renderer))
+        Ok((
+            Self { ctx, color_space },
+            renderer,
+        ))
     }
 
     fn sample_count(settings: &Settings) -> u32 {
@@ -52,17 +56,16 @@ impl iced_graphics::window::GLCompositor for Compositor {
         );
     }
 
-    fn draw<T: AsRef<str>>(
+    fn present<T: AsRef<str>>(
         &mut self,
         renderer: &mut Self::Renderer,
         viewport: &Viewport,
         color: Color,
-        output: &<Self::Renderer as iced_native::Renderer>::Output,
         overlay: &[T],
-    ) -> mouse::Interaction {
+    ) {
         let gl = &mut self.ctx;
 
-        let [red, green, blue, alpha] = color.into_linear();
+        let [red, green, blue, alpha] = self.color_space.clear_color(color);
 
         let clear_color = solstice::Color {
             red,
@@ -79,6 +82,8 @@ impl iced_graphics::window::GLCompositor for Compositor {
             },
         );
 
-        renderer.backend_mut().draw(gl, viewport, output, overlay)
+        renderer.with_primitives(|backend, primitives| {
+            backend.present(gl, primitives, viewport, overlay);
+        });
     }
 }
@@ -0,0 +1,86 @@
+#[cfg(target_os = "linux")]
+mod common;
+
+use iced_solstice::settings::ColorSpace;
+use iced_solstice::{Color, Settings};
+
+#[test]
+fn srgb_is_the_default() {
+    assert_eq!(Settings::default().color_space, ColorSpace::Srgb);
+}
+
+#[test]
+fn srgb_clears_with_linear_components() {
+    let color = Color::from_rgba(0.5, 0.25, 1.0, 0.5);
+
+    assert_eq!(ColorSpace::Srgb.clear_color(color), color.into_linear());
+}
+
+#[test]
+fn legacy_gamma_clears_with_encoded_components() {
+    let color = Color::from_rgba(0.5, 0.25, 1.0, 0.5);
+
+    assert_eq!(
+        ColorSpace::LegacyGamma.clear_color(color),
+        [0.5, 0.25, 1.0, 0.5]
+    );
+}
+
+#[test]
+fn clear_color_preserves_alpha_and_extremes() {
+    for color_space in [ColorSpace::Srgb, ColorSpace::LegacyGamma] {
+        assert_eq!(color_space.clear_color(Color::BLACK), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(color_space.clear_color(Color::WHITE), [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(color_space.clear_color(Color::TRANSPARENT)[3], 0.0);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod headless {
+    use super::common;
+    use iced_graphics::Primitive;
+    use iced_native::{Background, Color, Rectangle, Size};
+    use iced_solstice::settings::ColorSpace;
+    use iced_solstice::{Renderer, Settings};
+
+    /// A black quad at 50% alpha over a white one gives a mid gray, blended in
+    /// linear space with [`ColorSpace::Srgb`] and on the encoded values with
+    /// [`ColorSpace::LegacyGamma`].
+    #[test]
+    fn half_transparent_quads_blend_in_their_color_space() {
+        for (color_space, expected) in [(ColorSpace::Srgb, 188), (ColorSpace::LegacyGamma, 128)] {
+            let settings = Settings {
+                color_space,
+                ..Settings::default()
+            };
+            let draw = |renderer: &mut Renderer| {
+                renderer.draw_primitive(quad(Color::WHITE));
+                renderer.draw_primitive(quad(Color::from_rgba(0.0, 0.0, 0.0, 0.5)));
+            };
+
+            let snapshot = common::render(Size::new(32, 32), settings, 1.0, draw, &[]);
+
+            let center = ((snapshot.height / 2 * snapshot.width + snapshot.width / 2) * 4) as usize;
+
+            for channel in &snapshot.pixels[center..center + 3] {
+                assert!(
+                    (*channel as i16 - expected).abs() <= 2,
+                    "{:?}: expected {} in every channel, got {:?}",
+                    color_space,
+                    expected,
+                    &snapshot.pixels[center..center + 4]
+                );
+            }
+        }
+    }
+
+    fn quad(color: Color) -> Primitive {
+        Primitive::Quad {
+            bounds: Rectangle::new([0.0, 0.0].into(), [32.0, 32.0].into()),
+            background: Background::Color(color),
+            border_radius: 0.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use glutin::platform::unix::HeadlessContextExt;
+use iced_graphics::Viewport;
+use iced_native::Size;
+use iced_solstice::{Backend, Renderer, Settings, Snapshot};
+
+/// Renders whatever `draw` queues on a headless OSMesa context of the given
+/// physical size.
+pub fn render(
+    size: Size<u32>,
+    settings: Settings,
+    scale_factor: f64,
+    draw: impl FnOnce(&mut Renderer),
+    overlay: &[&str],
+) -> Snapshot {
+    let context = glutin::ContextBuilder::new()
+        .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (3, 3)))
+        .build_osmesa(glutin::dpi::PhysicalSize::new(size.width, size.height))
+        .expect("Create headless OSMesa context");
+
+    let context = unsafe { context.make_current() }.expect("Make context current");
+
+    let gl = unsafe {
+        solstice::glow::Context::from_loader_function(|name| {
+            context.get_proc_address(name) as *const _
+        })
+    };
+    let mut gl = solstice::Context::new(gl);
+
+    let mut renderer = Renderer::new(Backend::new(&mut gl, settings));
+    let viewport = Viewport::with_physical_size(size, scale_factor);
+
+    draw(&mut renderer);
+
+    renderer.with_primitives(|backend, primitives| {
+        backend.render_offscreen(&mut gl, primitives, &viewport, overlay)
+    })
+}
@@ -0,0 +1,485 @@
+//! Visual regression tests for the renderer.
+//!
+//! Every scene is rendered on a headless OSMesa context and compared against a
+//! PNG in `tests/golden`. Differing scenes write a diff image to
+//! `target/golden-diffs`.
+//!
+//! A scene without a golden fails. To record missing goldens, or regenerate
+//! all of them after an intended change, run:
+//!
+//! ```text
+//! ICED_SOLSTICE_UPDATE_GOLDENS=1 cargo test --test golden
+//! ```
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use iced_graphics::triangle::{Mesh2D, Vertex2D};
+use iced_graphics::Primitive;
+use iced_native::alignment;
+use iced_native::{Background, Color, Font, Rectangle, Size, Vector};
+use iced_solstice::decoration::{
+    Border, BorderStyle, ColorStop, Decorate, Decoration, Edge, Gradient, Shadow,
+};
+use iced_solstice::{Renderer, Settings, Snapshot};
+use std::path::{Path, PathBuf};
+
+/// The maximum difference allowed for a single channel of a pixel.
+const CHANNEL_TOLERANCE: u8 = 2;
+
+/// The maximum fraction of pixels that may exceed [`CHANNEL_TOLERANCE`].
+const PIXEL_TOLERANCE: f64 = 0.001;
+
+const SIZE: Size<u32> = Size {
+    width: 256,
+    height: 256,
+};
+
+#[test]
+fn rounded_bordered_quads() {
+    let primitives = vec![
+        quad(Rectangle::new([16.0, 16.0].into(), [96.0, 64.0].into()), 0.0, 0.0),
+        quad(Rectangle::new([144.0, 16.0].into(), [96.0, 64.0].into()), 12.0, 0.0),
+        quad(Rectangle::new([16.0, 112.0].into(), [96.0, 64.0].into()), 0.0, 4.0),
+        quad(Rectangle::new([144.0, 112.0].into(), [96.0, 96.0].into()), 48.0, 6.0),
+    ];
+
+    assert_golden("rounded_bordered_quads", &primitives, 1.0, &[]);
+    assert_golden("rounded_bordered_quads_hidpi", &primitives, 1.5, &[]);
+}
+
+#[test]
+fn meshes_with_clip_bounds() {
+    let mesh = || Primitive::Mesh2D {
+        buffers: Mesh2D {
+            vertices: vec![
+                Vertex2D {
+                    position: [0.0, 0.0],
+                    color: [1.0, 0.0, 0.0, 1.0],
+                },
+                Vertex2D {
+                    position: [200.0, 40.0],
+                    color: [0.0, 1.0, 0.0, 1.0],
+                },
+                Vertex2D {
+                    position: [40.0, 200.0],
+                    color: [0.0, 0.0, 1.0, 1.0],
+                },
+            ],
+            indices: vec![0, 1, 2],
+        },
+        size: Size::new(200.0, 200.0),
+    };
+
+    let primitives = vec![
+        Primitive::Translate {
+            translation: Vector::new(8.0, 8.0),
+            content: Box::new(mesh()),
+        },
+        Primitive::Clip {
+            bounds: Rectangle::new([128.0, 128.0].into(), [96.0, 96.0].into()),
+            offset: Vector::new(0, 0),
+            content: Box::new(Primitive::Translate {
+                translation: Vector::new(96.0, 96.0),
+                content: Box::new(mesh()),
+            }),
+        },
+    ];
+
+    assert_golden("meshes_with_clip_bounds", &primitives, 1.0, &[]);
+}
+
+#[test]
+fn multi_font_text() {
+    let primitives = vec![
+        text("Hello, solstice!", Font::Default, 20.0, 16.0),
+        text(
+            &iced_graphics::font::CHECKMARK_ICON.to_string(),
+            iced_graphics::font::ICONS,
+            32.0,
+            56.0,
+        ),
+        text(
+            "The quick brown fox jumps over the lazy dog",
+            Font::Default,
+            14.0,
+            112.0,
+        ),
+    ];
+
+    assert_golden("multi_font_text", &primitives, 1.0, &[]);
+    assert_golden("multi_font_text_hidpi", &primitives, 2.0, &[]);
+}
+
+#[test]
+fn measured_wrapping_matches_drawn_wrapping() {
+    use iced_native::text::Renderer as _;
+
+    const CONTENT: &str = "The quick brown fox jumps over the lazy dog";
+    const SCALE_FACTOR: f64 = 1.25;
+
+    let mut measured = (0.0, 0.0);
+
+    // The text gets more room than measured, so any additional wrapping when
+    // drawing would show up below the measured height
+    let snapshot = render(
+        |renderer| {
+            measured = renderer.measure(CONTENT, 16, Font::Default, Size::new(100.0, 1000.0));
+
+            renderer.draw_primitive(Primitive::Text {
+                content: CONTENT.to_string(),
+                bounds: Rectangle::new([16.0, 16.0].into(), [measured.0, 200.0].into()),
+                color: Color::BLACK,
+                size: 16.0,
+                font: Font::Default,
+                horizontal_alignment: alignment::Horizontal::Left,
+                vertical_alignment: alignment::Vertical::Top,
+            });
+        },
+        SCALE_FACTOR,
+        &[],
+    );
+
+    let (width, height) = measured;
+    assert!(height > 32.0, "the text should wrap into several lines");
+
+    let scale = SCALE_FACTOR as f32;
+    let right = ((16.0 + width) * scale).ceil() as u32 + 1;
+    let bottom = ((16.0 + height) * scale).ceil() as u32 + 1;
+
+    for (i, pixel) in snapshot.pixels.chunks_exact(4).enumerate() {
+        let (x, y) = (i as u32 % snapshot.width, i as u32 / snapshot.width);
+
+        assert!(
+            pixel[3] == 0 || (x <= right && y <= bottom),
+            "text drawn at ({}, {}), outside of its measured bounds of {:?}",
+            x,
+            y,
+            measured
+        );
+    }
+}
+
+#[test]
+fn overlay_text() {
+    let primitives = vec![quad(
+        Rectangle::new([0.0, 0.0].into(), [256.0, 256.0].into()),
+        0.0,
+        0.0,
+    )];
+
+    assert_golden(
+        "overlay_text",
+        &primitives,
+        1.0,
+        &["Debug overlay", "Rendered on top"],
+    );
+}
+
+#[test]
+fn linear_gradients() {
+    let stops = || {
+        vec![
+            ColorStop::new(0.0, Color::from_rgb(0.9, 0.2, 0.1)),
+            ColorStop::new(0.5, Color::from_rgb(0.9, 0.8, 0.1)),
+            ColorStop::new(1.0, Color::from_rgb(0.1, 0.3, 0.9)),
+        ]
+    };
+
+    assert_decorated_golden(
+        "linear_gradients",
+        &[
+            (
+                Rectangle::new([16.0, 16.0].into(), [224.0, 64.0].into()),
+                gradient(Gradient::linear(0.0, stops())),
+            ),
+            (
+                Rectangle::new([16.0, 96.0].into(), [96.0, 144.0].into()),
+                gradient(Gradient::linear(std::f32::consts::FRAC_PI_2, stops())),
+            ),
+            (
+                Rectangle::new([144.0, 96.0].into(), [96.0, 144.0].into()),
+                Decoration {
+                    border_radius: 24.0.into(),
+                    ..gradient(Gradient::linear(std::f32::consts::FRAC_PI_4, stops()))
+                },
+            ),
+        ],
+    );
+}
+
+#[test]
+fn radial_gradients() {
+    let stops = || {
+        vec![
+            ColorStop::new(0.0, Color::WHITE),
+            ColorStop::new(1.0, Color::from_rgba(0.2, 0.4, 0.8, 0.0)),
+        ]
+    };
+
+    assert_decorated_golden(
+        "radial_gradients",
+        &[
+            (
+                Rectangle::new([16.0, 16.0].into(), [224.0, 96.0].into()),
+                gradient(Gradient::radial([0.5, 0.5].into(), 48.0, stops())),
+            ),
+            (
+                Rectangle::new([16.0, 128.0].into(), [224.0, 112.0].into()),
+                Decoration {
+                    border_radius: 16.0.into(),
+                    ..gradient(Gradient::radial([0.0, 1.0].into(), 160.0, stops()))
+                },
+            ),
+        ],
+    );
+}
+
+#[test]
+fn offset_blurred_shadows() {
+    let card = |offset: Vector, blur_radius: f32, spread: f32| Decoration {
+        fill: Color::WHITE.into(),
+        border_radius: 8.0.into(),
+        shadow: Some(Shadow {
+            offset,
+            blur_radius,
+            spread,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.5),
+        }),
+        ..Decoration::default()
+    };
+
+    assert_decorated_golden(
+        "offset_blurred_shadows",
+        &[
+            (
+                Rectangle::new([24.0, 24.0].into(), [80.0, 64.0].into()),
+                card(Vector::new(6.0, 6.0), 0.0, 0.0),
+            ),
+            (
+                Rectangle::new([152.0, 24.0].into(), [80.0, 64.0].into()),
+                card(Vector::new(0.0, 8.0), 12.0, 0.0),
+            ),
+            (
+                Rectangle::new([24.0, 152.0].into(), [80.0, 64.0].into()),
+                card(Vector::new(-4.0, 4.0), 8.0, 4.0),
+            ),
+            (
+                Rectangle::new([152.0, 152.0].into(), [80.0, 64.0].into()),
+                card(Vector::new(0.0, 0.0), 16.0, -4.0),
+            ),
+        ],
+    );
+}
+
+#[test]
+fn asymmetric_corner_radii() {
+    let rounded = |radii: [f32; 4]| Decoration {
+        fill: Color::from_rgb(0.2, 0.4, 0.8).into(),
+        border_radius: radii.into(),
+        border: Border::new(3.0, Color::from_rgb(0.9, 0.6, 0.1)),
+        ..Decoration::default()
+    };
+
+    assert_decorated_golden(
+        "asymmetric_corner_radii",
+        &[
+            (
+                Rectangle::new([16.0, 16.0].into(), [96.0, 96.0].into()),
+                rounded([0.0, 24.0, 0.0, 24.0]),
+            ),
+            (
+                Rectangle::new([144.0, 16.0].into(), [96.0, 96.0].into()),
+                rounded([48.0, 0.0, 8.0, 16.0]),
+            ),
+            (
+                Rectangle::new([16.0, 144.0].into(), [224.0, 96.0].into()),
+                rounded([48.0, 48.0, 0.0, 0.0]),
+            ),
+        ],
+    );
+}
+
+#[test]
+fn styled_borders_with_mixed_widths() {
+    let edge = |width: f32, color: Color| Edge { width, color };
+    let mixed = |style: BorderStyle| Decoration {
+        fill: Color::from_rgb(0.95, 0.95, 0.9).into(),
+        border_radius: [0.0, 16.0, 0.0, 16.0].into(),
+        border: Border {
+            top: edge(2.0, Color::from_rgb(0.9, 0.2, 0.1)),
+            right: edge(6.0, Color::from_rgb(0.1, 0.6, 0.2)),
+            bottom: edge(4.0, Color::from_rgb(0.1, 0.3, 0.9)),
+            left: edge(1.0, Color::BLACK),
+            style,
+        },
+        ..Decoration::default()
+    };
+
+    assert_decorated_golden(
+        "styled_borders_with_mixed_widths",
+        &[
+            (
+                Rectangle::new([16.0, 16.0].into(), [224.0, 64.0].into()),
+                mixed(BorderStyle::Solid),
+            ),
+            (
+                Rectangle::new([16.0, 96.0].into(), [224.0, 64.0].into()),
+                mixed(BorderStyle::Dashed {
+                    dash: 12.0,
+                    gap: 6.0,
+                }),
+            ),
+            (
+                Rectangle::new([16.0, 176.0].into(), [224.0, 64.0].into()),
+                mixed(BorderStyle::Dotted),
+            ),
+        ],
+    );
+}
+
+fn gradient(gradient: Gradient) -> Decoration {
+    Decoration {
+        fill: gradient.into(),
+        ..Decoration::default()
+    }
+}
+
+fn quad(bounds: Rectangle, border_radius: f32, border_width: f32) -> Primitive {
+    Primitive::Quad {
+        bounds,
+        background: Background::Color(Color::from_rgb(0.2, 0.4, 0.8)),
+        border_radius,
+        border_width,
+        border_color: Color::from_rgb(0.9, 0.6, 0.1),
+    }
+}
+
+fn text(content: &str, font: Font, size: f32, y: f32) -> Primitive {
+    Primitive::Text {
+        content: content.to_string(),
+        bounds: Rectangle::new([16.0, y].into(), [224.0, 96.0].into()),
+        color: Color::BLACK,
+        size,
+        font,
+        horizontal_alignment: alignment::Horizontal::Left,
+        vertical_alignment: alignment::Vertical::Top,
+    }
+}
+
+fn decorated(quads: &[(Rectangle, Decoration)]) -> impl FnOnce(&mut Renderer) + '_ {
+    move |renderer| {
+        for (bounds, decoration) in quads {
+            renderer.fill_decorated(*bounds, decoration);
+        }
+    }
+}
+
+fn assert_golden(name: &str, primitives: &[Primitive], scale_factor: f64, overlay: &[&str]) {
+    let draw = |renderer: &mut Renderer| {
+        for primitive in primitives {
+            renderer.draw_primitive(primitive.clone());
+        }
+    };
+
+    assert_snapshot(
+        name,
+        common::render(SIZE, Settings::default(), scale_factor, draw, overlay),
+    );
+}
+
+fn assert_decorated_golden(name: &str, quads: &[(Rectangle, Decoration)]) {
+    assert_snapshot(
+        name,
+        common::render(SIZE, Settings::default(), 1.0, decorated(quads), &[]),
+    );
+}
+
+fn assert_snapshot(name: &str, snapshot: Snapshot) {
+    let actual = image::RgbaImage::from_raw(snapshot.width, snapshot.height, snapshot.pixels)
+        .expect("Snapshot size");
+
+    let path = golden_path(name);
+    let update = std::env::var_os("ICED_SOLSTICE_UPDATE_GOLDENS").is_some();
+
+    if update {
+        std::fs::create_dir_all(path.parent().unwrap()).expect("Create golden directory");
+        actual.save(&path).expect("Write golden");
+
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "{}: golden {} is missing, record it with ICED_SOLSTICE_UPDATE_GOLDENS=1",
+        name,
+        path.display()
+    );
+
+    let expected = image::open(&path).expect("Read golden").to_rgba8();
+
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "{}: golden has different dimensions",
+        name
+    );
+
+    let mut diff = image::RgbaImage::new(actual.width(), actual.height());
+    let mut mismatches = 0;
+
+    for ((expected, actual), diff) in expected
+        .pixels()
+        .zip(actual.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let exceeds = expected
+            .0
+            .iter()
+            .zip(actual.0.iter())
+            .any(|(a, b)| (*a as i16 - *b as i16).abs() as u8 > CHANNEL_TOLERANCE);
+
+        *diff = if exceeds {
+            mismatches += 1;
+            image::Rgba([255, 0, 255, 255])
+        } else {
+            image::Rgba([actual[0] / 4, actual[1] / 4, actual[2] / 4, 255])
+        };
+    }
+
+    let total = (actual.width() * actual.height()) as f64;
+
+    if mismatches as f64 / total > PIXEL_TOLERANCE {
+        let diff_path = diff_path(name);
+
+        diff.save(&diff_path).expect("Write diff");
+        actual
+            .save(diff_path.with_extension("actual.png"))
+            .expect("Write actual");
+
+        panic!(
+            "{}: {} pixels differ from the golden, see {}",
+            name,
+            mismatches,
+            diff_path.display()
+        );
+    }
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(name)
+        .with_extension("png")
+}
+
+fn diff_path(name: &str) -> PathBuf {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join("golden-diffs");
+
+    std::fs::create_dir_all(&directory).expect("Create diff directory");
+
+    directory.join(name).with_extension("png")
+}